@@ -0,0 +1,207 @@
+#!/usr/bin/env perl
+# Generates src/regex/unicode_tables.rs from the Unicode Character Database that ships
+# with Perl, so that every table comes from the same Unicode version:
+#
+#     perl scripts/generate_unicode_tables.pl > src/regex/unicode_tables.rs
+#     rustfmt src/regex/unicode_tables.rs
+#
+# Bump UNICODE_VERSION along with the Perl it's run with to update the tables.
+
+use strict;
+use warnings;
+use Unicode::UCD qw(prop_invlist prop_invmap prop_values
+    prop_value_aliases casespec);
+
+use constant UNICODE_VERSION => '14.0.0';
+
+die 'Unicode::UCD has Unicode ' . Unicode::UCD::UnicodeVersion() . ', expected ' . UNICODE_VERSION . "\n"
+    unless Unicode::UCD::UnicodeVersion() eq UNICODE_VERSION;
+
+# `Caucasian_Albanian` becomes `CaucasianAlbanian`
+sub variant {
+    join '', map { ucfirst } split /_/, shift;
+}
+
+# Names are compared ignoring case, spaces, underscores and hyphens
+sub loose {
+    my $name = lc shift;
+    $name =~ s/[ _-]//g;
+    $name;
+}
+
+sub hex_list {
+    join ', ', map { sprintf '0x%04X', $_ } @_;
+}
+
+# Every char having a property
+sub chars {
+    my @list = prop_invlist(shift);
+    my @chars;
+    while (my ($start, $end) = splice @list, 0, 2) {
+        push @chars, $start .. ($end // 0x110000) - 1;
+    }
+    @chars;
+}
+
+# Inclusive ranges of the chars having a property
+sub ranges {
+    my @list = prop_invlist(shift);
+    push @list, 0x110000 if @list % 2;
+
+    my @ranges;
+    while (my ($start, $end) = splice @list, 0, 2) {
+        push @ranges, sprintf '(0x%04X, 0x%04X)', $start, $end - 1;
+    }
+    @ranges;
+}
+
+# Runs of chars with the same value of `property`, as their starts and values
+sub runs {
+    my ($starts, $values) = prop_invmap(shift);
+
+    # The last run is past the last char
+    pop @$starts;
+    pop @$values;
+    ($starts, $values);
+}
+
+# The single char `code_point` maps to with `mapping`, the full lowercase or uppercase
+# mapping, or `undef` if it maps to itself or to several chars
+sub case_mapping {
+    my ($code_point, $mapping) = @_;
+    my $simple = Unicode::UCD::charinfo($code_point)->{$mapping};
+    my $special = casespec($code_point);
+
+    my $is_unconditional = $special && $special->{code} && !defined $special->{condition};
+    my $full = $is_unconditional ? $special->{$mapping} : $simple;
+    return undef if !$full || $full =~ / /;
+
+    my $mapped = hex $full;
+    $mapped == $code_point ? undef : $mapped;
+}
+
+my $version = UNICODE_VERSION =~ s/\.0$//r;
+my @out;
+
+push @out, "//! Unicode $version tables, generated by `scripts/generate_unicode_tables.pl` from the";
+push @out, '//! Unicode Character Database.';
+push @out, '';
+push @out, 'use crate::regex::unicode::GeneralCategory;';
+push @out, 'use crate::regex::unicode::GeneralCategory::*;';
+push @out, '';
+
+# `Katakana_Or_Hiragana` has no aliases since no char has it as its script
+my @script_aliases = grep { @$_ } map { [prop_value_aliases('Script', $_)] } prop_values('Script');
+my %seen_scripts;
+my @scripts = sort grep { !$seen_scripts{$_}++ } map { variant($_->[1]) } @script_aliases;
+
+push @out, '/// Scripts usable with `\p{...}`, the script of a char being `Unknown` if it has none';
+push @out, '#[derive(Debug, PartialEq, Clone, Copy)]';
+push @out, '#[allow(clippy::enum_variant_names)] // `KhitanSmallScript` is the name of the script';
+push @out, 'pub enum Script {';
+push @out, map { "    $_," } @scripts;
+push @out, '}';
+push @out, '';
+
+my @script_names;
+for my $aliases (@script_aliases) {
+    my %seen_names;
+    push @script_names, map { [$_, variant($aliases->[1])] }
+        grep { !$seen_names{$_}++ } map { loose($_) } @$aliases;
+}
+@script_names = sort { $a->[0] cmp $b->[0] } @script_names;
+
+push @out, '/// Loose names of every script, sorted, along with its short and other names';
+push @out, 'pub const SCRIPT_NAMES: [(&str, Script); ' . @script_names . '] = [';
+push @out, map { "    (\"$_->[0]\", Script::$_->[1])," } @script_names;
+push @out, '];';
+push @out, '';
+
+my ($category_starts, $categories) = runs('General_Category');
+
+push @out, '/// Start of every run of chars of the same general category, the first one being 0';
+push @out, 'pub const GENERAL_CATEGORY_RUN_STARTS: [u32; ' . @$category_starts . '] = [';
+push @out, '    ' . hex_list(@$category_starts) . ',';
+push @out, '];';
+push @out, '';
+push @out, '/// General category of the run starting at the same index of';
+push @out, '/// `GENERAL_CATEGORY_RUN_STARTS`';
+push @out, 'pub const GENERAL_CATEGORY_RUNS: [GeneralCategory; ' . @$categories . '] = [';
+push @out, '    ' . join(', ', @$categories) . ',';
+push @out, '];';
+push @out, '';
+
+my ($script_starts, $scripts) = runs('Script');
+
+push @out, '/// Start of every run of chars of the same script, the first one being 0';
+push @out, 'pub const SCRIPT_RUN_STARTS: [u32; ' . @$script_starts . '] = [';
+push @out, '    ' . hex_list(@$script_starts) . ',';
+push @out, '];';
+push @out, '';
+push @out, '/// Script of the run starting at the same index of `SCRIPT_RUN_STARTS`';
+push @out, 'pub const SCRIPT_RUNS: [Script; ' . @$scripts . '] = [';
+push @out, map { '    Script::' . variant($_) . ',' } @$scripts;
+push @out, '];';
+push @out, '';
+
+my @digit_run_starts;
+my @digits = prop_invlist('General_Category=Nd');
+while (my ($start, $end) = splice @digits, 0, 2) {
+    die sprintf "digits from 0x%04X don't come in runs of 10\n", $start if ($end - $start) % 10;
+    for (my $run_start = $start; $run_start < $end; $run_start += 10) {
+        push @digit_run_starts, $run_start;
+    }
+}
+
+push @out, '/// First char of every run of 10 decimal digits (general category `Nd`), which always';
+push @out, '/// come in such runs, from `0` to `9`.';
+push @out, 'pub const DECIMAL_DIGIT_RUN_STARTS: [u32; ' . @digit_run_starts . '] = [';
+push @out, '    ' . hex_list(@digit_run_starts) . ',';
+push @out, '];';
+push @out, '';
+
+my @connector_punctuation = chars('General_Category=Pc');
+
+push @out, '/// Connector punctuation (general category `Pc`), part of `\w` like the underscore';
+push @out, 'pub const CONNECTOR_PUNCTUATION: [char; ' . @connector_punctuation . '] = [';
+push @out, '    ' . join(', ', map { $_ < 0x80 ? "'" . chr($_) . "'" : sprintf "'\\u{%04X}'", $_ }
+    @connector_punctuation) . ',';
+push @out, '];';
+
+for (
+    ['Alphabetic', 'ALPHABETIC'],
+    ['Uppercase', 'UPPERCASE'],
+    ['Lowercase', 'LOWERCASE'],
+    ['White_Space', 'WHITE_SPACE'],
+) {
+    my ($property, $table) = @$_;
+    my @ranges = ranges($property);
+
+    push @out, '';
+    push @out, "/// Inclusive ranges of the chars with the `$property` property";
+    push @out, "pub const ${table}_RANGES: [(u32, u32); " . @ranges . '] = [';
+    push @out, '    ' . join(', ', @ranges) . ',';
+    push @out, '];';
+}
+
+my @case_mappings;
+my %changes_case = map { $_ => 1 } chars('Changes_When_Lowercased'),
+    chars('Changes_When_Uppercased');
+for my $code_point (sort { $a <=> $b } keys %changes_case) {
+    my $lowercase = case_mapping($code_point, 'lower');
+    my $uppercase = case_mapping($code_point, 'upper');
+    next unless defined $lowercase || defined $uppercase;
+
+    push @case_mappings, sprintf '(0x%04X, 0x%04X, 0x%04X)', $code_point,
+        $lowercase // $code_point, $uppercase // $code_point;
+}
+
+push @out, '';
+push @out, '/// Chars with a lowercase or uppercase form of a single other char, sorted, along with';
+push @out, '/// both forms, a char being its own form if it has no other. Forms longer than a char,';
+push @out, '/// like the uppercase `SS` of `ß`, are left out.';
+push @out, 'pub static CASE_MAPPINGS: [(u32, u32, u32); ' . @case_mappings . '] = [';
+push @out, '    ' . join(', ', @case_mappings) . ',';
+push @out, '];';
+
+print join("\n", @out), "\n";
@@ -0,0 +1,503 @@
+use crate::grep::{describe, STDIN_NAME};
+use crate::walk::Recursion;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+pub const USAGE: &str = "Usage: grep [OPTION]... PATTERNS [FILE]...";
+
+pub const HELP: &str = "\
+Search for PATTERNS in each FILE.
+Example: grep -i 'hello world' menu.h main.c
+PATTERNS can contain multiple patterns separated by newlines.
+
+Pattern selection and interpretation:
+  -E, --extended-regexp     PATTERNS are extended regular expressions (always on)
+  -e, --regexp=PATTERNS     use PATTERNS for matching
+  -f, --file=FILE           take PATTERNS from FILE
+  -i, --ignore-case         ignore case distinctions in patterns and data
+      --no-ignore-case      do not ignore case distinctions (default)
+  -w, --word-regexp         match only whole words
+  -x, --line-regexp         match only whole lines
+
+Miscellaneous:
+  -s, --no-messages         suppress error messages
+  -v, --invert-match        select non-matching lines
+  -V, --version             display version information and exit
+      --help                display this help text and exit
+
+Output control:
+  -n, --line-number         print line number with output lines
+  -H, --with-filename       print file name with output lines
+  -h, --no-filename         suppress the file name prefix on output
+  -q, --quiet, --silent     suppress all normal output
+  -l, --files-with-matches  print only names of FILEs with selected lines
+  -c, --count               print only a count of selected lines per FILE
+
+File and directory selection:
+  -r, --recursive           search directories, following symlinks only when
+                            they are command-line operands
+  -R, --dereference-recursive  likewise, but follow all symlinks
+      --include=GLOB        search only files whose base name matches GLOB
+      --exclude=GLOB        skip files whose base name matches GLOB
+      --exclude-dir=GLOB    skip directories whose base name matches GLOB
+
+When FILE is '-', read standard input. With no FILE, read '.' if
+recursive, '-' otherwise.
+Exit status is 0 if any line is selected, 1 otherwise;
+if any error occurs and -q is not given, the exit status is 2.";
+
+#[derive(Debug, Error)]
+pub enum ArgsError {
+    #[error("invalid option -- '{0}'")]
+    InvalidOption(char),
+    #[error("unrecognized option '{0}'")]
+    UnrecognizedOption(String),
+    #[error("option requires an argument -- '{0}'")]
+    MissingArgument(String),
+    #[error("option '{0}' doesn't allow an argument")]
+    UnexpectedArgument(String),
+    #[error("no pattern given")]
+    NoPattern,
+    /// Only file operands can be any bytes, options and patterns are text
+    #[error("argument '{}' isn't valid UTF-8", .0.to_string_lossy())]
+    InvalidUtf8(OsString),
+}
+
+/// Reading a `-f` file failed, which unlike the errors of the command line itself isn't
+/// followed by the usage
+#[derive(Debug, Error)]
+#[error("{}: {}", .0.display(), describe(.1))]
+pub struct PatternFileError(PathBuf, io::Error);
+
+/// What the command line asks for
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Search(Config),
+    Help,
+    Version,
+}
+
+/// Options of a search, as given on the command line
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    /// A line is selected if any of these matches
+    pub patterns: Vec<String>,
+    /// `-f` files, `-` standing for the standard input, whose lines are added to
+    /// `patterns` by `load_pattern_files` once the command line is parsed
+    pub pattern_files: Vec<PathBuf>,
+    /// Files to search, `-` standing for the standard input. Empty when searching the
+    /// working directory recursively without operands.
+    pub files: Vec<PathBuf>,
+    pub recursion: Recursion,
+    /// Globs selecting files and directories by name, see `Walker`
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub exclude_dir: Vec<String>,
+    pub ignore_case: bool,
+    pub invert_match: bool,
+    pub word_regexp: bool,
+    pub line_regexp: bool,
+    pub line_number: bool,
+    /// `None` when neither `-H` nor `-h` is given, the name is then printed only when
+    /// searching several files
+    pub with_filename: Option<bool>,
+    pub quiet: bool,
+    pub files_with_matches: bool,
+    pub count: bool,
+    pub no_messages: bool,
+}
+
+impl Config {
+    /// Names are printed by default when there may be several files to search
+    pub fn with_filename(&self) -> bool {
+        self.with_filename.unwrap_or_else(|| {
+            let searches_directory = self.recursion != Recursion::Off
+                && (self.files.is_empty() || self.files.iter().any(|file| file.is_dir()));
+
+            self.files.len() > 1 || searches_directory
+        })
+    }
+
+    /// Adds every line of the `-f` files to the patterns. Only `\n` ends a line, so a
+    /// `\r` before it is part of the pattern, like in GNU grep.
+    pub fn load_pattern_files(&mut self) -> Result<(), PatternFileError> {
+        for path in &self.pattern_files {
+            let patterns = if path.as_os_str() == STDIN_NAME {
+                io::read_to_string(io::stdin())
+            } else {
+                fs::read_to_string(path)
+            }
+            .map_err(|error| PatternFileError(path.clone(), error))?;
+
+            // An empty file has no lines and matches nothing, while a file of one empty
+            // line matches everything
+            if !patterns.is_empty() {
+                let patterns = patterns.strip_suffix('\n').unwrap_or(&patterns);
+                self.patterns.extend(split_patterns(patterns));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses the arguments following the program name. Like GNU grep, options may come
+/// after operands, unless they follow `--`.
+pub fn parse_args(args: impl IntoIterator<Item = OsString>) -> Result<Command, ArgsError> {
+    let mut args = args.into_iter();
+    let mut config = Config::default();
+    let mut operands = Vec::new();
+    let mut has_pattern_option = false;
+
+    while let Some(arg) = args.next() {
+        let arg = match arg.into_string() {
+            Ok(arg) => arg,
+            Err(arg) if arg.as_encoded_bytes().starts_with(b"-") => {
+                return Err(ArgsError::InvalidUtf8(arg))
+            }
+            Err(arg) => {
+                operands.push(arg);
+                continue;
+            }
+        };
+
+        if arg == "--" {
+            operands.extend(args.by_ref());
+            break;
+        }
+
+        if let Some(long_option) = arg.strip_prefix("--") {
+            let (name, value) = match long_option.split_once('=') {
+                Some((name, value)) => (name, Some(OsString::from(value))),
+                None => (long_option, None),
+            };
+
+            let option = match name {
+                "help" => return Ok(Command::Help),
+                "version" => return Ok(Command::Version),
+                "regexp" => OptionKind::Regexp,
+                "file" => OptionKind::File,
+                "extended-regexp" => OptionKind::Flag('E'),
+                "ignore-case" => OptionKind::Flag('i'),
+                "no-ignore-case" => OptionKind::NoIgnoreCase,
+                "word-regexp" => OptionKind::Flag('w'),
+                "line-regexp" => OptionKind::Flag('x'),
+                "no-messages" => OptionKind::Flag('s'),
+                "invert-match" => OptionKind::Flag('v'),
+                "line-number" => OptionKind::Flag('n'),
+                "with-filename" => OptionKind::Flag('H'),
+                "no-filename" => OptionKind::Flag('h'),
+                "quiet" | "silent" => OptionKind::Flag('q'),
+                "files-with-matches" => OptionKind::Flag('l'),
+                "count" => OptionKind::Flag('c'),
+                "recursive" => OptionKind::Flag('r'),
+                "dereference-recursive" => OptionKind::Flag('R'),
+                "include" => OptionKind::Include,
+                "exclude" => OptionKind::Exclude,
+                "exclude-dir" => OptionKind::ExcludeDir,
+                _ => return Err(ArgsError::UnrecognizedOption(arg)),
+            };
+
+            match option {
+                OptionKind::Flag(_) | OptionKind::NoIgnoreCase if value.is_some() => {
+                    return Err(ArgsError::UnexpectedArgument(arg));
+                }
+                OptionKind::Flag(flag) => apply_flag(&mut config, flag)?,
+                OptionKind::NoIgnoreCase => config.ignore_case = false,
+                _ => {
+                    let value = match value {
+                        Some(value) => value,
+                        None => args
+                            .next()
+                            .ok_or_else(|| ArgsError::MissingArgument(name.to_string()))?,
+                    };
+
+                    has_pattern_option |= option.is_pattern_option();
+                    apply_value_option(&mut config, &option, value)?;
+                }
+            }
+
+            continue;
+        }
+
+        // A lone `-` is the standard input, not an option
+        let Some(short_options) = arg.strip_prefix('-').filter(|options| !options.is_empty())
+        else {
+            operands.push(arg.into());
+            continue;
+        };
+
+        // Short options can be combined, like `-inr`, and the ones taking a value take the
+        // rest of the argument or the next one, like `-epattern` or `-e pattern`
+        for (i, flag) in short_options.char_indices() {
+            let option = match flag {
+                'V' => return Ok(Command::Version),
+                'e' => OptionKind::Regexp,
+                'f' => OptionKind::File,
+                _ => {
+                    apply_flag(&mut config, flag)?;
+                    continue;
+                }
+            };
+
+            let rest = &short_options[i + flag.len_utf8()..];
+            let value = if rest.is_empty() {
+                args.next()
+                    .ok_or_else(|| ArgsError::MissingArgument(flag.to_string()))?
+            } else {
+                OsString::from(rest)
+            };
+
+            apply_value_option(&mut config, &option, value)?;
+            has_pattern_option = true;
+            break;
+        }
+    }
+
+    let mut operands = operands.into_iter();
+    if !has_pattern_option {
+        let pattern = operands.next().ok_or(ArgsError::NoPattern)?;
+        let pattern = pattern.into_string().map_err(ArgsError::InvalidUtf8)?;
+        config.patterns.extend(split_patterns(&pattern));
+    }
+
+    config.files = operands.map(PathBuf::from).collect();
+    if config.files.is_empty() && config.recursion == Recursion::Off {
+        config.files.push(PathBuf::from(STDIN_NAME));
+    }
+
+    Ok(Command::Search(config))
+}
+
+/// What a recognized option does, options taking a value being named after their long form
+enum OptionKind {
+    Regexp,
+    File,
+    Include,
+    Exclude,
+    ExcludeDir,
+    NoIgnoreCase,
+    /// Option turning on a setting, by its short form
+    Flag(char),
+}
+
+impl OptionKind {
+    fn is_pattern_option(&self) -> bool {
+        matches!(self, OptionKind::Regexp | OptionKind::File)
+    }
+}
+
+fn apply_flag(config: &mut Config, flag: char) -> Result<(), ArgsError> {
+    match flag {
+        // Patterns are always extended regular expressions
+        'E' => {}
+        'i' | 'y' => config.ignore_case = true,
+        'w' => config.word_regexp = true,
+        'x' => config.line_regexp = true,
+        's' => config.no_messages = true,
+        'v' => config.invert_match = true,
+        'n' => config.line_number = true,
+        'H' => config.with_filename = Some(true),
+        'h' => config.with_filename = Some(false),
+        'q' => config.quiet = true,
+        'l' => config.files_with_matches = true,
+        'c' => config.count = true,
+        'r' => config.recursion = Recursion::Recursive,
+        'R' => config.recursion = Recursion::FollowSymlinks,
+        _ => return Err(ArgsError::InvalidOption(flag)),
+    }
+
+    Ok(())
+}
+
+fn apply_value_option(
+    config: &mut Config,
+    option: &OptionKind,
+    value: OsString,
+) -> Result<(), ArgsError> {
+    // Pattern files are the only values that are paths
+    if let OptionKind::File = option {
+        config.pattern_files.push(PathBuf::from(value));
+        return Ok(());
+    }
+
+    let value = value.into_string().map_err(ArgsError::InvalidUtf8)?;
+    match option {
+        OptionKind::Include => config.include.push(value),
+        OptionKind::Exclude => config.exclude.push(value),
+        OptionKind::ExcludeDir => config.exclude_dir.push(value),
+        _ => config.patterns.extend(split_patterns(&value)),
+    }
+
+    Ok(())
+}
+
+/// Patterns separated by newlines are searched for independently
+fn split_patterns(patterns: &str) -> impl Iterator<Item = String> + '_ {
+    patterns.split('\n').map(str::to_string)
+}
+
+#[cfg(test)]
+mod parse_args_tests {
+    use crate::args::{parse_args, ArgsError, Command, Config};
+    use crate::walk::Recursion;
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    fn parse(args: &[&str]) -> Result<Command, ArgsError> {
+        parse_args(args.iter().map(OsString::from))
+    }
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    fn parse_config(args: &[&str]) -> Config {
+        match parse(args) {
+            Ok(Command::Search(config)) => config,
+            other => panic!("expected a search, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn pattern_and_files() {
+        let config = parse_config(&["-E", "a+", "x.txt", "y.txt"]);
+
+        assert_eq!(vec!["a+"], config.patterns);
+        assert_eq!(paths(&["x.txt", "y.txt"]), config.files);
+        assert!(config.with_filename());
+    }
+
+    #[test]
+    fn stdin_without_files() {
+        let config = parse_config(&["a"]);
+
+        assert_eq!(paths(&["-"]), config.files);
+        assert!(!config.with_filename());
+    }
+
+    #[test]
+    fn combined_short_flags() {
+        let config = parse_config(&["-inv", "a"]);
+
+        assert!(config.ignore_case && config.line_number && config.invert_match);
+    }
+
+    #[test]
+    fn long_options() {
+        let config = parse_config(&["--ignore-case", "--count", "--no-filename", "a", "f", "g"]);
+
+        assert!(config.ignore_case && config.count);
+        assert!(!config.with_filename());
+    }
+
+    #[test]
+    fn options_after_operands() {
+        let config = parse_config(&["a", "file", "-c"]);
+
+        assert!(config.count);
+        assert_eq!(paths(&["file"]), config.files);
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let config = parse_config(&["--", "-v", "-c"]);
+
+        assert_eq!(vec!["-v"], config.patterns);
+        assert_eq!(paths(&["-c"]), config.files);
+        assert!(!config.invert_match && !config.count);
+    }
+
+    #[test]
+    fn repeated_regexp_options() {
+        let config = parse_config(&["-e", "a", "-eb", "--regexp=c", "--regexp", "d", "file"]);
+
+        assert_eq!(vec!["a", "b", "c", "d"], config.patterns);
+        assert_eq!(paths(&["file"]), config.files);
+    }
+
+    #[test]
+    fn regexp_option_value_after_flags() {
+        let config = parse_config(&["-ie", "-v"]);
+
+        assert_eq!(vec!["-v"], config.patterns);
+        assert!(config.ignore_case && !config.invert_match);
+    }
+
+    #[test]
+    fn newlines_separate_patterns() {
+        let config = parse_config(&["a\nb"]);
+
+        assert_eq!(vec!["a", "b"], config.patterns);
+    }
+
+    #[test]
+    fn recursion_and_file_filters() {
+        let config = parse_config(&["-rn", "--include=*.rs", "--exclude", "*.md", "a"]);
+
+        assert_eq!(Recursion::Recursive, config.recursion);
+        assert_eq!(vec!["*.rs"], config.include);
+        assert_eq!(vec!["*.md"], config.exclude);
+        // The working directory is searched, with file names
+        assert!(config.files.is_empty());
+        assert!(config.with_filename());
+
+        let config = parse_config(&["--dereference-recursive", "--exclude-dir=.git", "a"]);
+
+        assert_eq!(Recursion::FollowSymlinks, config.recursion);
+        assert_eq!(vec![".git"], config.exclude_dir);
+    }
+
+    #[test]
+    fn help_and_version() {
+        assert!(matches!(parse(&["--help"]), Ok(Command::Help)));
+        assert!(matches!(parse(&["-V"]), Ok(Command::Version)));
+        assert!(matches!(parse(&["a", "--version"]), Ok(Command::Version)));
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert!(matches!(
+            parse(&["-k", "a"]),
+            Err(ArgsError::InvalidOption('k'))
+        ));
+        assert!(matches!(
+            parse(&["--frobnicate", "a"]),
+            Err(ArgsError::UnrecognizedOption(_))
+        ));
+        assert!(matches!(
+            parse(&["--count=3", "a"]),
+            Err(ArgsError::UnexpectedArgument(_))
+        ));
+        assert!(matches!(parse(&["-e"]), Err(ArgsError::MissingArgument(_))));
+        assert!(matches!(parse(&[]), Err(ArgsError::NoPattern)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_arguments() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let invalid = || OsString::from_vec(b"\xFF".to_vec());
+
+        match parse_args([OsString::from("a"), invalid()]) {
+            Ok(Command::Search(config)) => assert_eq!(vec![PathBuf::from(invalid())], config.files),
+            other => panic!("expected a search, got {other:?}"),
+        }
+        assert!(matches!(
+            parse_args([invalid()]),
+            Err(ArgsError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn missing_pattern_file_fails_once_loaded() {
+        let mut config = parse_config(&["-f", "/nonexistent/patterns.txt"]);
+
+        assert_eq!(paths(&["/nonexistent/patterns.txt"]), config.pattern_files);
+        assert!(config.load_pattern_files().is_err());
+    }
+}
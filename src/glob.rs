@@ -0,0 +1,149 @@
+use codecrafters_grep::{Regex, RegexParsingError};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Shell wildcard pattern matching file names, as taken by `--include` and the other
+/// file filters. `*` matches any run of chars, `?` any single char, `[...]` a char of a
+/// set, `[!...]` or `[^...]` a char outside of it, and `\` makes the next char literal.
+pub struct Glob {
+    regex: Regex,
+}
+
+impl Glob {
+    /// Fails for sets the regex syntax rejects too, like the range of `[z-a]` or the
+    /// class of `[[:alfa:]]`
+    pub fn new(glob: &str) -> Result<Self, RegexParsingError> {
+        Ok(Self {
+            regex: Regex::new(&to_regex(glob))?,
+        })
+    }
+
+    /// Whether the whole of `name` matches
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.matches(name)
+    }
+}
+
+/// Anchored regex matching what `glob` does. A `[` without its closing `]` is literal.
+fn to_regex(glob: &str) -> String {
+    let mut regex = String::from("(?s)^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(next_char) = chars.next() {
+        match next_char {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => match to_char_group(chars.clone()) {
+                Some((char_group, rest)) => {
+                    regex.push_str(&char_group);
+                    chars = rest;
+                }
+                None => push_literal(&mut regex, '['),
+            },
+            '\\' => push_literal(&mut regex, chars.next().unwrap_or('\\')),
+            _ => push_literal(&mut regex, next_char),
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+/// Translates the set following a `[`, returning it along with the glob left after its
+/// closing `]`, or `None` if there's no such `]`
+fn to_char_group(mut chars: Peekable<Chars<'_>>) -> Option<(String, Peekable<Chars<'_>>)> {
+    let mut char_group = String::from("[");
+
+    if chars
+        .next_if(|&next_char| next_char == '!' || next_char == '^')
+        .is_some()
+    {
+        char_group.push('^');
+    }
+
+    // A `]` right after the opening `[` is part of the set
+    if chars.next_if_eq(&']').is_some() {
+        char_group.push_str("\\]");
+    }
+
+    loop {
+        match chars.next()? {
+            ']' => break,
+            // POSIX classes like `[:alpha:]` mean the same in regexes
+            '[' if chars.next_if_eq(&':').is_some() => {
+                char_group.push_str("[:");
+                loop {
+                    let next_char = chars.next()?;
+                    if next_char == ':' && chars.next_if_eq(&']').is_some() {
+                        break;
+                    }
+                    char_group.push(next_char);
+                }
+                char_group.push_str(":]");
+            }
+            '-' => char_group.push('-'),
+            '\\' => push_literal(&mut char_group, chars.next()?),
+            next_char => push_literal(&mut char_group, next_char),
+        }
+    }
+
+    char_group.push(']');
+    Some((char_group, chars))
+}
+
+/// Escapes every char that could be a regex metachar, escaped punctuation being literal
+fn push_literal(regex: &mut String, literal: char) {
+    if !literal.is_alphanumeric() {
+        regex.push('\\');
+    }
+    regex.push(literal);
+}
+
+#[cfg(test)]
+mod glob_tests {
+    use crate::glob::Glob;
+
+    fn glob(glob: &str) -> Glob {
+        Glob::new(glob).unwrap()
+    }
+
+    #[test]
+    fn wildcards() {
+        assert!(glob("*.rs").matches("main.rs"));
+        assert!(glob("*.rs").matches(".rs"));
+        assert!(!glob("*.rs").matches("main.rs.bak"));
+        assert!(glob("?.txt").matches("a.txt"));
+        assert!(!glob("?.txt").matches("ab.txt"));
+    }
+
+    #[test]
+    fn metachars_are_literal() {
+        assert!(glob("a+b(1).txt").matches("a+b(1).txt"));
+        assert!(!glob("a.txt").matches("abtxt"));
+        assert!(glob("\\*").matches("*"));
+        assert!(!glob("\\*").matches("a"));
+    }
+
+    #[test]
+    fn char_sets() {
+        assert!(glob("[ab].c").matches("b.c"));
+        assert!(!glob("[ab].c").matches("c.c"));
+        assert!(glob("[!ab].c").matches("c.c"));
+        assert!(glob("[^a-y]").matches("z"));
+        assert!(!glob("[^a-y]").matches("q"));
+        assert!(glob("[]x]").matches("]"));
+        assert!(glob("[[:digit:]]*").matches("1st"));
+    }
+
+    #[test]
+    fn unterminated_char_set_is_literal() {
+        assert!(glob("[ab").matches("[ab"));
+        assert!(!glob("[ab").matches("a"));
+    }
+
+    #[test]
+    fn invalid_char_sets() {
+        assert!(Glob::new("[z-a]").is_err());
+        assert!(Glob::new("[[:alfa:]]").is_err());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::args::Config;
+use codecrafters_grep::bytes::Regex;
+use codecrafters_grep::RegexParsingError;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// Name standing for the standard input in the list of files
+pub const STDIN_NAME: &str = "-";
+
+/// Name printed for lines of the standard input
+pub const STDIN_LABEL: &str = "(standard input)";
+
+pub enum SearchError {
+    /// Reading the input failed, the search can go on with the next file
+    Read(io::Error),
+    /// Writing the output failed, there's no point in searching further
+    Write(io::Error),
+}
+
+/// Searches the lines of every input in turn, printing the selected ones
+pub struct Searcher<W: Write> {
+    regexes: Vec<Regex>,
+    with_filename: bool,
+    config: Config,
+    output: W,
+}
+
+impl<W: Write> Searcher<W> {
+    /// Fails if any of the patterns of `config` isn't a valid regex
+    pub fn new(config: Config, output: W) -> Result<Self, RegexParsingError> {
+        let regexes = config
+            .patterns
+            .iter()
+            .map(|pattern| build_regex(pattern, &config))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            regexes,
+            with_filename: config.with_filename(),
+            config,
+            output,
+        })
+    }
+
+    pub fn search_stdin(&mut self) -> Result<bool, SearchError> {
+        self.search_reader(io::stdin().lock(), STDIN_LABEL)
+    }
+
+    /// Searches the file at `path`, printing `name` for it. Returns whether any line was
+    /// selected.
+    pub fn search_file(&mut self, path: &Path, name: &str) -> Result<bool, SearchError> {
+        let file = File::open(path).map_err(SearchError::Read)?;
+
+        // Opening a directory works, but reading it doesn't
+        if file.metadata().map_err(SearchError::Read)?.is_dir() {
+            return Err(SearchError::Read(io::Error::other("Is a directory")));
+        }
+
+        self.search_reader(BufReader::new(file), name)
+    }
+
+    /// Lines are read as bytes, so files don't have to be valid UTF-8. Returns whether any
+    /// line was selected.
+    pub fn search_reader(
+        &mut self,
+        mut reader: impl BufRead,
+        name: &str,
+    ) -> Result<bool, SearchError> {
+        let mut line = Vec::new();
+        let mut line_number = 0;
+        let mut selected_count = 0;
+
+        loop {
+            line.clear();
+            if reader
+                .read_until(b'\n', &mut line)
+                .map_err(SearchError::Read)?
+                == 0
+            {
+                break;
+            }
+
+            if line.last() == Some(&b'\n') {
+                line.pop();
+            }
+            line_number += 1;
+
+            if !self.is_selected(&line) {
+                continue;
+            }
+            selected_count += 1;
+
+            // The rest of the input can't change what gets printed
+            if self.config.quiet {
+                break;
+            }
+            if self.config.files_with_matches {
+                writeln!(self.output, "{name}").map_err(SearchError::Write)?;
+                break;
+            }
+
+            if !self.config.count {
+                self.print_line(&line, name, line_number)
+                    .map_err(SearchError::Write)?;
+            }
+        }
+
+        if self.config.count && !self.config.files_with_matches && !self.config.quiet {
+            self.print_count(selected_count, name)
+                .map_err(SearchError::Write)?;
+        }
+
+        Ok(selected_count > 0)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+
+    /// A line is selected if any pattern matches it, or if none does with `-v`
+    fn is_selected(&self, line: &[u8]) -> bool {
+        self.regexes.iter().any(|regex| regex.matches(line)) != self.config.invert_match
+    }
+
+    fn print_line(&mut self, line: &[u8], name: &str, line_number: usize) -> io::Result<()> {
+        if self.with_filename {
+            write!(self.output, "{name}:")?;
+        }
+        if self.config.line_number {
+            write!(self.output, "{line_number}:")?;
+        }
+
+        self.output.write_all(line)?;
+        self.output.write_all(b"\n")
+    }
+
+    fn print_count(&mut self, count: usize, name: &str) -> io::Result<()> {
+        if self.with_filename {
+            write!(self.output, "{name}:")?;
+        }
+
+        writeln!(self.output, "{count}")
+    }
+}
+
+/// Compiles `pattern` with the matching options of `config`. The pattern is checked on its
+/// own first, so errors aren't about the wrapping added for `-w` or `-x`. Neither
+/// wrapping needs backtracking, so the pattern can still be matched by the NFA.
+fn build_regex(pattern: &str, config: &Config) -> Result<Regex, RegexParsingError> {
+    // An empty pattern matches every line
+    let pattern = if pattern.is_empty() {
+        "(?:)"
+    } else {
+        Regex::new(pattern)?;
+        pattern
+    };
+
+    let mut pattern = if config.line_regexp {
+        format!("^(?:{pattern})$")
+    } else if config.word_regexp {
+        format!(r"\b{{start-half}}(?:{pattern})\b{{end-half}}")
+    } else {
+        pattern.to_string()
+    };
+
+    if config.ignore_case {
+        pattern.insert_str(0, "(?i)");
+    }
+
+    Regex::new(&pattern)
+}
+
+/// Error message without the "(os error N)" suffix, like "No such file or directory"
+pub fn describe(error: &io::Error) -> String {
+    let message = error.to_string();
+
+    match message.find(" (os error") {
+        Some(suffix) => message[..suffix].to_string(),
+        None => message,
+    }
+}
@@ -1,5 +1,13 @@
 
 mod regex;
 
+pub use regex::builder::RegexBuilder;
+pub use regex::error::RegexParsingError;
 pub use regex::regex::Regex;
+pub use regex::replace::Replacer;
+pub use regex::search::{CaptureMatches, Captures, Match, Matches, Split, SplitN};
+
+pub mod bytes {
+    pub use crate::regex::bytes::Regex;
+}
 
@@ -1,24 +1,128 @@
+mod args;
+mod glob;
+mod grep;
+mod walk;
 
 use std::env;
 use std::io;
+use std::io::BufWriter;
+use std::path::PathBuf;
 use std::process;
-use codecrafters_grep::Regex;
+use args::{parse_args, Command, HELP, USAGE};
+use grep::{describe, SearchError, Searcher, STDIN_LABEL};
+use walk::{WalkEntry, Walker};
+
+/// Exit statuses of GNU grep
+const EXIT_MATCH: i32 = 0;
+const EXIT_NO_MATCH: i32 = 1;
+const EXIT_ERROR: i32 = 2;
 
 fn main() {
-    if env::args().nth(1).unwrap() != "-E" {
-        println!("Expected first argument to be '-E'");
-        process::exit(1);
+    process::exit(run());
+}
+
+fn run() -> i32 {
+    let mut config = match parse_args(env::args_os().skip(1)) {
+        Ok(Command::Search(config)) => config,
+        Ok(Command::Help) => {
+            println!("{USAGE}\n{HELP}");
+            return EXIT_MATCH;
+        }
+        Ok(Command::Version) => {
+            println!("grep {}", env!("CARGO_PKG_VERSION"));
+            return EXIT_MATCH;
+        }
+        Err(error) => {
+            eprintln!("grep: {error}\n{USAGE}\nTry 'grep --help' for more information.");
+            return EXIT_ERROR;
+        }
+    };
+
+    if let Err(error) = config.load_pattern_files() {
+        eprintln!("grep: {error}");
+        return EXIT_ERROR;
     }
 
-    let pattern = env::args().nth(2).unwrap();
-    let mut input_line = String::new();
+    let walker = match Walker::new(&config) {
+        Ok(walker) => walker,
+        Err(error) => {
+            eprintln!("grep: {error}");
+            return EXIT_ERROR;
+        }
+    };
+
+    // Without operands, `-r` searches the working directory
+    let operands: Vec<Option<PathBuf>> = if config.files.is_empty() {
+        vec![None]
+    } else {
+        config.files.iter().cloned().map(Some).collect()
+    };
+    let quiet = config.quiet;
+    let no_messages = config.no_messages;
+
+    let output = BufWriter::new(io::stdout().lock());
+    let mut searcher = match Searcher::new(config, output) {
+        Ok(searcher) => searcher,
+        Err(error) => {
+            eprintln!("grep: {error}");
+            return EXIT_ERROR;
+        }
+    };
+    let mut matched = false;
+    let mut had_error = false;
+
+    'operands: for operand in &operands {
+        for entry in walker.walk(operand.as_deref()) {
+            let (result, name) = match &entry {
+                WalkEntry::Stdin => (searcher.search_stdin(), STDIN_LABEL),
+                WalkEntry::File(path, name) => (searcher.search_file(path, name), name.as_str()),
+                WalkEntry::Error(name, error) => {
+                    if !no_messages {
+                        eprintln!("grep: {name}: {}", describe(error));
+                    }
+                    had_error = true;
+                    continue;
+                }
+                WalkEntry::Loop(name) => {
+                    if !no_messages {
+                        eprintln!("grep: {name}: warning: recursive directory loop");
+                    }
+                    continue;
+                }
+            };
+
+            match result {
+                Ok(file_matched) => matched |= file_matched,
+                Err(SearchError::Read(error)) => {
+                    if !no_messages {
+                        eprintln!("grep: {name}: {}", describe(&error));
+                    }
+                    had_error = true;
+                }
+                Err(SearchError::Write(error)) => {
+                    eprintln!("grep: write error: {}", describe(&error));
+                    return EXIT_ERROR;
+                }
+            }
 
-    io::stdin().read_line(&mut input_line).unwrap();
+            // Nothing is printed with `-q`, the first selected line settles the exit status
+            if quiet && matched {
+                break 'operands;
+            }
+        }
+    }
+
+    if let Err(error) = searcher.flush() {
+        eprintln!("grep: write error: {}", describe(&error));
+        return EXIT_ERROR;
+    }
 
-    let regex = Regex::new(&pattern).unwrap();
-    if regex.matches(&input_line) {
-        process::exit(0)
+    // Like GNU grep, an error wins over having found matches elsewhere, unless with `-q`
+    if had_error && !(quiet && matched) {
+        EXIT_ERROR
+    } else if matched {
+        EXIT_MATCH
     } else {
-        process::exit(1)
+        EXIT_NO_MATCH
     }
 }
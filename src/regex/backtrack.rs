@@ -0,0 +1,649 @@
+use crate::regex::input::{Input, Unit};
+use crate::regex::pattern::{
+    eq_ignore_case, is_end_before_newline, is_end_of_line, is_start_of_line, is_word_boundary,
+    is_word_end_half, is_word_start_half, max_sequence_width, Captures, Pattern, Quantifier,
+};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// Matches `patterns` starting at `start`, returning where the match ends. Alternatives
+/// and quantifiers are tried in order of preference, and the first way leading to a match
+/// wins. `captures` is set to the spans of the groups of that match.
+///
+/// What's left to match is kept as a linked list on the heap, and the ways not tried yet
+/// on a stack, so the input length never sets the recursion depth.
+pub fn matches_at(
+    patterns: &VecDeque<Pattern>,
+    input: Input,
+    start: usize,
+    captures: &mut Captures,
+) -> Option<usize> {
+    let mut backtracker = Backtracker {
+        input,
+        choices: Vec::new(),
+        captures: std::mem::take(captures),
+    };
+
+    let end = backtracker.run(
+        start,
+        push(Step::Sequence(Sequence::Deque(patterns, 0)), None),
+    );
+    *captures = backtracker.captures;
+    end
+}
+
+/// Patterns left to match in a group or alternation branch
+#[derive(Clone, Copy)]
+enum Sequence<'p> {
+    Deque(&'p VecDeque<Pattern>, usize),
+    Slice(&'p [Pattern]),
+}
+
+impl<'p> Sequence<'p> {
+    fn split_first(self) -> Option<(&'p Pattern, Sequence<'p>)> {
+        match self {
+            Sequence::Deque(patterns, index) => patterns
+                .get(index)
+                .map(|pattern| (pattern, Sequence::Deque(patterns, index + 1))),
+            Sequence::Slice(patterns) => patterns
+                .split_first()
+                .map(|(pattern, rest)| (pattern, Sequence::Slice(rest))),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Step<'p> {
+    Pattern(&'p Pattern),
+    Sequence(Sequence<'p>),
+    /// The group with this number, which started at `start`, ends here
+    CloseGroup {
+        index: usize,
+        start: usize,
+    },
+    /// Starts one more repetition, `count` having been matched already
+    Repeat {
+        quantifier: Quantifier<'p>,
+        count: usize,
+    },
+    /// A repetition that started at `start` ended here, `count` having been matched before
+    Repeated {
+        quantifier: Quantifier<'p>,
+        count: usize,
+        start: usize,
+    },
+    /// An atomic group or possessive quantifier matched, forget the other ways it could
+    /// have matched, i.e. the choices made since there were `choices` of them
+    EndAtomic {
+        choices: usize,
+    },
+    /// The body of a lookaround probed at `position` matched
+    EndLookaround {
+        choices: usize,
+        position: usize,
+        behind: bool,
+        negative: bool,
+    },
+}
+
+/// Steps left to match, shared between the choice points that resume from them
+type Continuation<'p> = Option<Rc<Frame<'p>>>;
+
+struct Frame<'p> {
+    step: Step<'p>,
+    next: Continuation<'p>,
+}
+
+fn push<'p>(step: Step<'p>, next: Continuation<'p>) -> Continuation<'p> {
+    Some(Rc::new(Frame { step, next }))
+}
+
+/// A way of matching not tried yet, along with the captures to restore when trying it
+struct ChoicePoint<'p> {
+    choice: Choice<'p>,
+    captures: Captures,
+}
+
+enum Choice<'p> {
+    Resume {
+        position: usize,
+        continuation: Continuation<'p>,
+    },
+    /// A greedy repetition of a single char pattern that reached `end` gives back one
+    /// char, as long as it stays at or after `min_end`
+    GiveBack {
+        min_end: usize,
+        end: usize,
+        continuation: Continuation<'p>,
+    },
+    /// A lazy repetition of a single char pattern that stopped at `end` takes one more
+    /// char, `remaining` more at most
+    TakeMore {
+        pattern: &'p Pattern,
+        remaining: Option<usize>,
+        end: usize,
+        continuation: Continuation<'p>,
+    },
+}
+
+struct Backtracker<'p, 'h> {
+    input: Input<'h>,
+    choices: Vec<ChoicePoint<'p>>,
+    captures: Captures,
+}
+
+impl<'p> Backtracker<'p, '_> {
+    fn run(&mut self, mut position: usize, mut continuation: Continuation<'p>) -> Option<usize> {
+        loop {
+            let Some(frame) = continuation else {
+                return Some(position);
+            };
+            continuation = frame.next.clone();
+
+            if !self.step(frame.step, &mut position, &mut continuation) {
+                (position, continuation) = self.backtrack()?;
+            }
+        }
+    }
+
+    /// Takes the most recent choice point, `None` once there's none left
+    fn backtrack(&mut self) -> Option<(usize, Continuation<'p>)> {
+        loop {
+            let ChoicePoint { choice, captures } = self.choices.pop()?;
+
+            match choice {
+                Choice::Resume {
+                    position,
+                    continuation,
+                } => {
+                    self.captures = captures;
+                    return Some((position, continuation));
+                }
+
+                Choice::GiveBack {
+                    min_end,
+                    end,
+                    continuation,
+                } => {
+                    let previous_unit = self.input.previous_unit(end);
+                    let end = end - previous_unit.map_or(0, Unit::len);
+
+                    if end > min_end {
+                        self.push_choice(
+                            Choice::GiveBack {
+                                min_end,
+                                end,
+                                continuation: continuation.clone(),
+                            },
+                            captures.clone(),
+                        );
+                    }
+
+                    self.captures = captures;
+                    return Some((end, continuation));
+                }
+
+                Choice::TakeMore {
+                    pattern,
+                    remaining,
+                    end,
+                    continuation,
+                } => {
+                    let Some(next_unit) = self.input.next_unit(end) else {
+                        continue;
+                    };
+                    if !pattern.matches_unit(next_unit) {
+                        continue;
+                    }
+
+                    let end = end + next_unit.len();
+                    let remaining = remaining.map(|remaining| remaining - 1);
+
+                    if remaining != Some(0) {
+                        self.push_choice(
+                            Choice::TakeMore {
+                                pattern,
+                                remaining,
+                                end,
+                                continuation: continuation.clone(),
+                            },
+                            captures.clone(),
+                        );
+                    }
+
+                    self.captures = captures;
+                    return Some((end, continuation));
+                }
+            }
+        }
+    }
+
+    fn push_choice(&mut self, choice: Choice<'p>, captures: Captures) {
+        self.choices.push(ChoicePoint { choice, captures });
+    }
+
+    fn push_resume(&mut self, position: usize, continuation: Continuation<'p>) {
+        self.push_choice(
+            Choice::Resume {
+                position,
+                continuation,
+            },
+            self.captures.clone(),
+        );
+    }
+
+    /// Runs one step, moving `position` and `continuation` on. Returns `false` if the step
+    /// failed and the matcher has to backtrack.
+    fn step(
+        &mut self,
+        step: Step<'p>,
+        position: &mut usize,
+        continuation: &mut Continuation<'p>,
+    ) -> bool {
+        match step {
+            Step::Pattern(pattern) => self.match_pattern(pattern, position, continuation),
+
+            Step::Sequence(mut sequence) => {
+                // Single chars are matched right away, without going through the continuation
+                while let Some((pattern, rest)) = sequence.split_first() {
+                    if !pattern.is_single_char() {
+                        if rest.split_first().is_some() {
+                            *continuation = push(Step::Sequence(rest), continuation.take());
+                        }
+                        return self.match_pattern(pattern, position, continuation);
+                    }
+
+                    if !self.match_char(pattern, position) {
+                        return false;
+                    }
+                    sequence = rest;
+                }
+
+                true
+            }
+
+            Step::CloseGroup { index, start } => {
+                self.captures[index] = Some((start, *position));
+                true
+            }
+
+            Step::Repeat { quantifier, count } => {
+                *continuation = push(
+                    Step::Repeated {
+                        quantifier,
+                        count,
+                        start: *position,
+                    },
+                    continuation.take(),
+                );
+                self.match_pattern(quantifier.pattern, position, continuation)
+            }
+
+            Step::Repeated {
+                quantifier,
+                count,
+                start,
+            } => {
+                // Like in Perl, an empty repetition is kept but ends the loop, since repeating
+                // it again couldn't make progress. Repetitions up to the minimum still happen.
+                if *position == start && count + 1 >= quantifier.min {
+                    return true;
+                }
+
+                self.repeat(quantifier, count + 1, position, continuation)
+            }
+
+            Step::EndAtomic { choices } => {
+                self.choices.truncate(choices);
+                true
+            }
+
+            Step::EndLookaround {
+                choices,
+                position: probed_at,
+                behind,
+                negative,
+            } => {
+                if behind && *position != probed_at {
+                    return false;
+                }
+
+                self.choices.truncate(choices);
+                *position = probed_at;
+                !negative
+            }
+        }
+    }
+
+    fn match_char(&self, pattern: &Pattern, position: &mut usize) -> bool {
+        match self.input.next_unit(*position) {
+            Some(next_unit) if pattern.matches_unit(next_unit) => {
+                *position += next_unit.len();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn match_pattern(
+        &mut self,
+        pattern: &'p Pattern,
+        position: &mut usize,
+        continuation: &mut Continuation<'p>,
+    ) -> bool {
+        if let Some(quantifier) = pattern.quantifier() {
+            return self.repeat(quantifier, 0, position, continuation);
+        }
+
+        let input = self.input;
+
+        match pattern {
+            Pattern::StartOfString => *position == 0,
+
+            Pattern::EndOfString => *position == input.len(),
+
+            Pattern::EndOfStringBeforeNewline => is_end_before_newline(input, *position),
+
+            Pattern::StartOfLine => is_start_of_line(input, *position),
+
+            Pattern::EndOfLine => is_end_of_line(input, *position),
+
+            Pattern::WordBoundary => is_word_boundary(input, *position, false),
+
+            Pattern::NotWordBoundary => !is_word_boundary(input, *position, false),
+
+            Pattern::WordStartHalf => is_word_start_half(input, *position, false),
+
+            Pattern::WordEndHalf => is_word_end_half(input, *position, false),
+
+            Pattern::AsciiWordBoundary => is_word_boundary(input, *position, true),
+
+            Pattern::AsciiNotWordBoundary => !is_word_boundary(input, *position, true),
+
+            Pattern::AsciiWordStartHalf => is_word_start_half(input, *position, true),
+
+            Pattern::AsciiWordEndHalf => is_word_end_half(input, *position, true),
+
+            Pattern::Group(index, group) => {
+                let close = push(
+                    Step::CloseGroup {
+                        index: *index,
+                        start: *position,
+                    },
+                    continuation.take(),
+                );
+                *continuation = push(Step::Sequence(Sequence::Deque(group, 0)), close);
+                true
+            }
+
+            Pattern::NonCapturingGroup(group) => {
+                *continuation = push(
+                    Step::Sequence(Sequence::Deque(group, 0)),
+                    continuation.take(),
+                );
+                true
+            }
+
+            Pattern::AtomicGroup(group) => {
+                let end = push(
+                    Step::EndAtomic {
+                        choices: self.choices.len(),
+                    },
+                    continuation.take(),
+                );
+                *continuation = push(Step::Sequence(Sequence::Deque(group, 0)), end);
+                true
+            }
+
+            Pattern::PossessiveQuantifier(quantifier) => {
+                let end = push(
+                    Step::EndAtomic {
+                        choices: self.choices.len(),
+                    },
+                    continuation.take(),
+                );
+                *continuation = push(Step::Pattern(quantifier), end);
+                true
+            }
+
+            Pattern::PositiveLookahead(group) => {
+                self.probe(group, position, false, false, continuation);
+                true
+            }
+
+            Pattern::NegativeLookahead(group) => {
+                self.probe(group, position, false, true, continuation);
+                true
+            }
+
+            Pattern::PositiveLookbehind(group) => {
+                self.probe(group, position, true, false, continuation);
+                true
+            }
+
+            Pattern::NegativeLookbehind(group) => {
+                self.probe(group, position, true, true, continuation);
+                true
+            }
+
+            Pattern::Alternation(alternation) => {
+                for variant in alternation.iter().skip(1).rev() {
+                    let resume_with = push(
+                        Step::Sequence(Sequence::Slice(variant)),
+                        continuation.clone(),
+                    );
+                    self.push_resume(*position, resume_with);
+                }
+
+                *continuation = push(
+                    Step::Sequence(Sequence::Slice(&alternation[0])),
+                    continuation.take(),
+                );
+                true
+            }
+
+            Pattern::Backreference(nth) => {
+                let Some(Some((start, end))) = self.captures.get(*nth).copied() else {
+                    return false;
+                };
+                let backreference = &input.as_bytes()[start..end];
+
+                if !input.as_bytes()[*position..].starts_with(backreference) {
+                    return false;
+                }
+
+                *position += backreference.len();
+                true
+            }
+
+            // Chars in another case may have another length, so they're compared one by one
+            Pattern::CaseInsensitiveBackreference(nth)
+            | Pattern::AsciiCaseInsensitiveBackreference(nth) => {
+                let Some(Some((start, end))) = self.captures.get(*nth).copied() else {
+                    return false;
+                };
+                let is_ascii_only =
+                    matches!(pattern, Pattern::AsciiCaseInsensitiveBackreference(_));
+                let mut rest = input.units(*position);
+                let mut end_of_match = *position;
+
+                for (_, captured_unit) in input.units(start).take_while(|&(i, _)| i < end) {
+                    let Some((i, input_unit)) = rest.next() else {
+                        return false;
+                    };
+
+                    let is_equal = match (captured_unit, input_unit) {
+                        (Unit::Char(captured_char), Unit::Char(input_char)) if is_ascii_only => {
+                            captured_char.eq_ignore_ascii_case(&input_char)
+                        }
+                        (Unit::Char(captured_char), Unit::Char(input_char)) => {
+                            eq_ignore_case(captured_char, input_char)
+                        }
+                        (captured_unit, input_unit) => captured_unit == input_unit,
+                    };
+                    if !is_equal {
+                        return false;
+                    }
+
+                    end_of_match = i + input_unit.len();
+                }
+
+                *position = end_of_match;
+                true
+            }
+
+            _ => self.match_char(pattern, position),
+        }
+    }
+
+    /// Sets up the probe of a lookaround at `position`. Its body is matched first, then
+    /// `EndLookaround` goes back to `position`, or fails if the lookaround is negative.
+    /// A negative lookaround succeeds when its body runs out of ways to match, which the
+    /// choice point pushed below the body's ones stands for.
+    fn probe(
+        &mut self,
+        group: &'p VecDeque<Pattern>,
+        position: &mut usize,
+        behind: bool,
+        negative: bool,
+        continuation: &mut Continuation<'p>,
+    ) {
+        let probed_at = *position;
+        let choices = self.choices.len();
+        let after = if negative {
+            self.push_resume(probed_at, continuation.take());
+            None
+        } else {
+            continuation.take()
+        };
+
+        let end = push(
+            Step::EndLookaround {
+                choices,
+                position: probed_at,
+                behind,
+                negative,
+            },
+            after,
+        );
+        let body = push(Step::Sequence(Sequence::Deque(group, 0)), end);
+
+        // Tries every start the length of the lookbehind allows, nearest first, which also
+        // allows lookbehinds of variable length. Their length is bounded when parsing.
+        let mut starts = vec![probed_at];
+        if behind {
+            let max_width = max_sequence_width(group).unwrap_or(usize::MAX);
+            let mut start = probed_at;
+
+            while starts.len() <= max_width {
+                let Some(previous_unit) = self.input.previous_unit(start) else {
+                    break;
+                };
+                start -= previous_unit.len();
+                starts.push(start);
+            }
+        }
+
+        for &start in starts.iter().skip(1).rev() {
+            self.push_resume(start, body.clone());
+        }
+
+        *position = starts[0];
+        *continuation = body;
+    }
+
+    /// Matches the quantifier from `count` repetitions on, starting with as many (greedy)
+    /// or as few (lazy) repetitions as possible
+    fn repeat(
+        &mut self,
+        quantifier: Quantifier<'p>,
+        count: usize,
+        position: &mut usize,
+        continuation: &mut Continuation<'p>,
+    ) -> bool {
+        if quantifier.pattern.is_single_char() {
+            return self.repeat_single_char(quantifier, position, continuation);
+        }
+
+        let can_stop = count >= quantifier.min;
+        let can_repeat = quantifier.max.map_or(true, |max| count < max);
+        let repeat =
+            |continuation: Continuation<'p>| push(Step::Repeat { quantifier, count }, continuation);
+
+        match (can_stop, can_repeat) {
+            (false, false) => return false,
+            (true, false) => {}
+            (false, true) => *continuation = repeat(continuation.take()),
+            (true, true) if quantifier.greedy => {
+                self.push_resume(*position, continuation.clone());
+                *continuation = repeat(continuation.take());
+            }
+            (true, true) => {
+                let resume_with = repeat(continuation.clone());
+                self.push_resume(*position, resume_with);
+            }
+        }
+
+        true
+    }
+
+    /// Single chars can't match in more than one way, so the reachable ends are found by
+    /// scanning, and a single choice point gives back or takes more chars one at a time
+    fn repeat_single_char(
+        &mut self,
+        quantifier: Quantifier<'p>,
+        position: &mut usize,
+        continuation: &mut Continuation<'p>,
+    ) -> bool {
+        let mut count = 0;
+        let mut end = *position;
+        let mut min_end = *position;
+        let limit = if quantifier.greedy {
+            quantifier.max
+        } else {
+            Some(quantifier.min)
+        };
+
+        for (_, next_unit) in self.input.units(*position) {
+            if limit.is_some_and(|limit| count >= limit)
+                || !quantifier.pattern.matches_unit(next_unit)
+            {
+                break;
+            }
+
+            count += 1;
+            end += next_unit.len();
+            if count == quantifier.min {
+                min_end = end;
+            }
+        }
+
+        if count < quantifier.min {
+            return false;
+        }
+
+        if quantifier.greedy && end > min_end {
+            self.push_choice(
+                Choice::GiveBack {
+                    min_end,
+                    end,
+                    continuation: continuation.clone(),
+                },
+                self.captures.clone(),
+            );
+        } else if !quantifier.greedy && quantifier.max != Some(quantifier.min) {
+            self.push_choice(
+                Choice::TakeMore {
+                    pattern: quantifier.pattern,
+                    remaining: quantifier.max.map(|max| max - quantifier.min),
+                    end,
+                    continuation: continuation.clone(),
+                },
+                self.captures.clone(),
+            );
+        }
+
+        *position = end;
+        true
+    }
+}
@@ -0,0 +1,47 @@
+use crate::regex::error::RegexParsingError;
+use crate::regex::regex::Regex;
+
+/// Roughly enough for a few thousand DFA states
+const DEFAULT_DFA_SIZE_LIMIT: usize = 2 * (1 << 20);
+
+#[derive(Debug, Clone)]
+pub struct RegexOptions {
+    pub dfa_size_limit: usize,
+    /// Set for `bytes::Regex`, see `Flags::raw_bytes`
+    pub raw_bytes: bool,
+}
+
+impl Default for RegexOptions {
+    fn default() -> Self {
+        Self {
+            dfa_size_limit: DEFAULT_DFA_SIZE_LIMIT,
+            raw_bytes: false,
+        }
+    }
+}
+
+/// Configures how a `Regex` is compiled and executed.
+pub struct RegexBuilder {
+    pattern: String,
+    options: RegexOptions,
+}
+
+impl RegexBuilder {
+    pub fn new(pattern: &str) -> Self {
+        Self {
+            pattern: pattern.to_string(),
+            options: RegexOptions::default(),
+        }
+    }
+
+    /// Approximate number of bytes the lazy DFA may use to cache states. The cache is
+    /// cleared when it's full, and searches fall back to the NFA when that keeps happening.
+    pub fn dfa_size_limit(&mut self, bytes: usize) -> &mut Self {
+        self.options.dfa_size_limit = bytes;
+        self
+    }
+
+    pub fn build(&self) -> Result<Regex, RegexParsingError> {
+        Regex::with_options(&self.pattern, self.options.clone())
+    }
+}
@@ -0,0 +1,32 @@
+use crate::regex::builder::RegexOptions;
+use crate::regex::error::RegexParsingError;
+use crate::regex::input::Input;
+
+/// Regex matching arbitrary bytes instead of UTF-8 text, for searching Latin-1 or binary
+/// data. Valid UTF-8 sequences in the input are matched as the chars they encode, any
+/// other byte can be matched with `.`, negated classes or, with Unicode off, with a byte
+/// escape like `(?-u)\xFF`. A byte escape never matches a char, whatever its code point.
+///
+/// Matching steps over whole chars where the input is valid UTF-8, so byte escapes only
+/// match bytes outside valid UTF-8: `(?-u)\xC3` doesn't match the first byte of `é`,
+/// encoded as `C3 A9`.
+pub struct Regex {
+    regex: crate::regex::regex::Regex,
+}
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Self, RegexParsingError> {
+        let options = RegexOptions {
+            raw_bytes: true,
+            ..RegexOptions::default()
+        };
+
+        Ok(Self {
+            regex: crate::regex::regex::Regex::with_options(pattern, options)?,
+        })
+    }
+
+    pub fn matches(&self, input: &[u8]) -> bool {
+        self.regex.is_match(Input::from(input))
+    }
+}
@@ -0,0 +1,353 @@
+use crate::regex::input::{Input, Unit};
+use crate::regex::nfa::{Instruction, Nfa};
+use std::collections::HashMap;
+use std::mem::size_of;
+
+const UNKNOWN_STATE: usize = usize::MAX;
+const ASCII_TRANSITIONS: usize = 128;
+
+/// How many times the cache may be cleared during a single search before giving up and
+/// letting the Pike VM take over.
+const MAX_CACHE_CLEARS: usize = 3;
+
+/// DFA built lazily from an NFA while searching: each DFA state is the set of NFA
+/// instructions reachable at a position, and transitions are computed the first time a
+/// unit is seen in a state, then cached across searches.
+pub struct LazyDfa {
+    states: Vec<State>,
+    state_ids: HashMap<Vec<usize>, usize>,
+    start_state: Option<usize>,
+    memory_usage: usize,
+    memory_limit: usize,
+}
+
+struct State {
+    /// Sorted `Char`, `Match` and not yet satisfied `AssertEndOfString` instructions
+    pcs: Vec<usize>,
+    is_match: bool,
+    ascii_transitions: Box<[usize; ASCII_TRANSITIONS]>,
+    transitions: HashMap<Unit, usize>,
+}
+
+impl LazyDfa {
+    pub fn new(memory_limit: usize) -> Self {
+        Self {
+            states: Vec::new(),
+            state_ids: HashMap::new(),
+            start_state: None,
+            memory_usage: 0,
+            memory_limit,
+        }
+    }
+
+    /// Returns `None` if the cache thrashed, meaning the search should be redone with the
+    /// Pike VM.
+    pub fn is_match(&mut self, nfa: &Nfa, input: Input) -> Option<bool> {
+        let mut cache_clears = 0;
+        let mut state = match self.start_state {
+            Some(start_state) => start_state,
+            None => {
+                let start_state = self.add_start_state(nfa)?;
+                self.start_state = Some(start_state);
+                start_state
+            }
+        };
+
+        for (_, input_unit) in input.units(0) {
+            if self.states[state].is_match {
+                return Some(true);
+            }
+
+            let cached_state = self.states[state].transition(input_unit);
+            state = if cached_state != UNKNOWN_STATE {
+                cached_state
+            } else {
+                if self.memory_usage > self.memory_limit {
+                    cache_clears += 1;
+                    if cache_clears > MAX_CACHE_CLEARS {
+                        return None;
+                    }
+
+                    state = self.clear_keeping(nfa, state);
+                }
+
+                self.add_transition(nfa, state, input_unit)?
+            };
+
+            if nfa.anchored_start && self.states[state].pcs.is_empty() {
+                return Some(false);
+            }
+        }
+
+        Some(self.states[state].is_match || self.matches_at_end(nfa, state, input.is_empty()))
+    }
+
+    fn add_start_state(&mut self, nfa: &Nfa) -> Option<usize> {
+        let mut closure = Closure::new(nfa);
+        closure.add(0, true, false);
+
+        self.add_state(nfa, closure.pcs)
+    }
+
+    fn add_transition(&mut self, nfa: &Nfa, from: usize, input_unit: Unit) -> Option<usize> {
+        let mut closure = Closure::new(nfa);
+
+        for &pc in &self.states[from].pcs {
+            if let Instruction::Char(pattern) = &nfa.instructions[pc] {
+                if pattern.matches_unit(input_unit) {
+                    closure.add(pc + 1, false, false);
+                }
+            }
+        }
+
+        // Unanchored search: a match can also start at the next position
+        if !nfa.anchored_start {
+            closure.add(0, false, false);
+        }
+
+        let to = self.add_state(nfa, closure.pcs)?;
+        let from_state = &mut self.states[from];
+
+        match ascii_index(input_unit) {
+            Some(index) => from_state.ascii_transitions[index] = to,
+            None => {
+                from_state.transitions.insert(input_unit, to);
+                self.memory_usage += size_of::<(Unit, usize)>();
+            }
+        }
+
+        Some(to)
+    }
+
+    fn add_state(&mut self, nfa: &Nfa, mut pcs: Vec<usize>) -> Option<usize> {
+        pcs.sort_unstable();
+
+        if let Some(&id) = self.state_ids.get(&pcs) {
+            return Some(id);
+        }
+
+        let state_size = size_of::<State>()
+            + size_of::<[usize; ASCII_TRANSITIONS]>()
+            + 2 * pcs.len() * size_of::<usize>();
+        if state_size > self.memory_limit {
+            return None;
+        }
+
+        self.memory_usage += state_size;
+        self.state_ids.insert(pcs.clone(), self.states.len());
+        self.states.push(State {
+            is_match: pcs
+                .iter()
+                .any(|&pc| nfa.instructions[pc] == Instruction::Match),
+            pcs,
+            ascii_transitions: Box::new([UNKNOWN_STATE; ASCII_TRANSITIONS]),
+            transitions: HashMap::new(),
+        });
+
+        Some(self.states.len() - 1)
+    }
+
+    /// Empties the cache except for `state`, which the search is currently in
+    fn clear_keeping(&mut self, nfa: &Nfa, state: usize) -> usize {
+        let pcs = std::mem::take(&mut self.states[state].pcs);
+
+        self.states.clear();
+        self.state_ids.clear();
+        self.start_state = None;
+        self.memory_usage = 0;
+
+        self.add_state(nfa, pcs)
+            .expect("state fit in the cache before clearing it")
+    }
+
+    /// End of string assertions can only be checked once the whole input was consumed
+    fn matches_at_end(&self, nfa: &Nfa, state: usize, at_start: bool) -> bool {
+        let mut closure = Closure::new(nfa);
+
+        for &pc in &self.states[state].pcs {
+            if nfa.instructions[pc] == Instruction::AssertEndOfString {
+                closure.add(pc + 1, at_start, true);
+            }
+        }
+
+        closure
+            .pcs
+            .iter()
+            .any(|&pc| nfa.instructions[pc] == Instruction::Match)
+    }
+}
+
+impl State {
+    fn transition(&self, input_unit: Unit) -> usize {
+        match ascii_index(input_unit) {
+            Some(index) => self.ascii_transitions[index],
+            None => self
+                .transitions
+                .get(&input_unit)
+                .copied()
+                .unwrap_or(UNKNOWN_STATE),
+        }
+    }
+}
+
+fn ascii_index(unit: Unit) -> Option<usize> {
+    match unit {
+        Unit::Char(char) if (char as usize) < ASCII_TRANSITIONS => Some(char as usize),
+        _ => None,
+    }
+}
+
+/// Set of NFA instructions reachable without consuming input
+struct Closure<'a> {
+    nfa: &'a Nfa,
+    pcs: Vec<usize>,
+    visited: Vec<bool>,
+}
+
+impl<'a> Closure<'a> {
+    fn new(nfa: &'a Nfa) -> Self {
+        Self {
+            nfa,
+            pcs: Vec::new(),
+            visited: vec![false; nfa.instructions.len()],
+        }
+    }
+
+    /// Adds every instruction reachable from `pc`. End of string assertions are kept
+    /// unresolved unless `at_end` is set.
+    fn add(&mut self, pc: usize, at_start: bool, at_end: bool) {
+        let mut stack = vec![pc];
+
+        while let Some(pc) = stack.pop() {
+            if self.visited[pc] {
+                continue;
+            }
+            self.visited[pc] = true;
+
+            match &self.nfa.instructions[pc] {
+                Instruction::Char(_) | Instruction::Match => self.pcs.push(pc),
+                Instruction::Split(first, second) => {
+                    stack.push(*second);
+                    stack.push(*first);
+                }
+                Instruction::Jump(target) => stack.push(*target),
+                // Only where a match ends matters to the DFA
+                // Which repetitions exit early doesn't change where matches end
+                Instruction::Save(_) | Instruction::ExitIfEmpty { .. } => stack.push(pc + 1),
+                Instruction::AssertStartOfString => {
+                    if at_start {
+                        stack.push(pc + 1);
+                    }
+                }
+                Instruction::AssertEndOfString => {
+                    if at_end {
+                        stack.push(pc + 1);
+                    } else {
+                        self.pcs.push(pc);
+                    }
+                }
+                Instruction::AssertWordBoundary { .. }
+                | Instruction::AssertNotWordBoundary { .. }
+                | Instruction::AssertWordStartHalf { .. }
+                | Instruction::AssertWordEndHalf { .. } => {
+                    unreachable!("word boundaries aren't DFA compatible")
+                }
+                Instruction::AssertStartOfLine
+                | Instruction::AssertEndOfLine
+                | Instruction::AssertEndOfStringBeforeNewline => {
+                    unreachable!("line anchors aren't DFA compatible")
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod lazy_dfa_tests {
+    use crate::regex::dfa::LazyDfa;
+    use crate::regex::nfa::Nfa;
+    use crate::regex::pattern::Pattern::*;
+    use std::collections::VecDeque;
+
+    fn error_timeout_nfa() -> Nfa {
+        // ^ERROR.+timeout$
+        let mut patterns = VecDeque::from([StartOfString]);
+        patterns.extend("ERROR".chars().map(CharLiteral));
+        patterns.push_back(OneOrMoreQuantifier(Box::new(Wildcard)));
+        patterns.extend("timeout".chars().map(CharLiteral));
+        patterns.push_back(EndOfString);
+
+        Nfa::compile(&patterns, 1).unwrap()
+    }
+
+    #[test]
+    fn anchored_pattern_matches() {
+        let mut dfa = LazyDfa::new(1 << 20);
+        let nfa = error_timeout_nfa();
+
+        assert_eq!(
+            Some(true),
+            dfa.is_match(&nfa, "ERROR: connection timeout".into())
+        );
+        assert_eq!(
+            Some(false),
+            dfa.is_match(&nfa, "ERROR: connection timeout!".into())
+        );
+        assert_eq!(
+            Some(false),
+            dfa.is_match(&nfa, "WARN ERROR: connection timeout".into())
+        );
+    }
+
+    #[test]
+    fn unanchored_pattern_matches() {
+        let mut dfa = LazyDfa::new(1 << 20);
+        let nfa = Nfa::compile(&VecDeque::from([CharLiteral('b'), DigitClass]), 1).unwrap();
+
+        assert_eq!(Some(true), dfa.is_match(&nfa, "aab1".into()));
+        assert_eq!(Some(false), dfa.is_match(&nfa, "aabb".into()));
+    }
+
+    #[test]
+    fn states_are_reused_across_searches() {
+        let mut dfa = LazyDfa::new(1 << 20);
+        let nfa = error_timeout_nfa();
+
+        dfa.is_match(&nfa, "ERROR: connection timeout".into());
+        let state_count = dfa.states.len();
+        dfa.is_match(&nfa, "ERROR: connection timeout".into());
+
+        assert_eq!(state_count, dfa.states.len());
+    }
+
+    #[test]
+    fn gives_up_when_no_state_fits() {
+        let mut dfa = LazyDfa::new(16);
+
+        assert_eq!(
+            None,
+            dfa.is_match(&error_timeout_nfa(), "ERROR: timeout".into())
+        );
+    }
+
+    #[test]
+    fn gives_up_when_cache_thrashes() {
+        let mut dfa = LazyDfa::new(4096);
+        let nfa = Nfa::compile(
+            &VecDeque::from([OneOrMoreQuantifier(Box::new(Group(
+                1,
+                VecDeque::from([Alternation(vec![
+                    vec![CharLiteral('a'), CharLiteral('b'), CharLiteral('c')],
+                    vec![CharLiteral('a'), DigitClass, Wildcard, CharLiteral('x')],
+                ])]),
+            )))]),
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(
+            None,
+            dfa.is_match(&nfa, "a1b2c3x".repeat(10).as_str().into())
+        );
+    }
+}
@@ -1,11 +1,53 @@
+use thiserror::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum RegexParsingError {
+    #[error("empty regular expression")]
     EmptyRegex,
+    #[error("trailing backslash")]
     InvalidCharClass,
-    InvalidStart,
-    InvalidEnd,
+    #[error("unmatched [")]
+    UnterminatedCharGroup,
+    #[error("unmatched (")]
+    UnterminatedGroup,
+    #[error("unmatched )")]
+    UnmatchedClosingParenthesis,
+    #[error("invalid range end in bracket expression")]
+    InvalidCharRange,
+    #[error("invalid character class name")]
+    InvalidPosixClass,
+    #[error("? doesn't follow anything to repeat")]
     InvalidOptionalQuantifier,
+    #[error("+ doesn't follow anything to repeat")]
     InvalidOneOrMoreQuantifier,
-    InvalidBackreference
+    #[error("* doesn't follow anything to repeat")]
+    InvalidZeroOrMoreQuantifier,
+    #[error("invalid backreference")]
+    InvalidBackreference,
+    #[error("invalid escape sequence")]
+    InvalidEscape,
+    #[error("invalid Unicode property")]
+    InvalidUnicodeProperty,
+    #[error("invalid group syntax")]
+    InvalidGroup,
+    #[error("lookbehind assertion has no maximum length")]
+    UnboundedLookbehind,
+    #[error("invalid flag")]
+    InvalidFlag,
+    #[error("invalid group name")]
+    InvalidGroupName,
+    #[error("duplicate group name")]
+    DuplicateGroupName,
+    #[error("backreference to undefined group name")]
+    UndefinedGroupName,
+    #[error("backreference to undefined group number")]
+    UndefinedGroupNumber,
+    #[error("{{...}} doesn't follow anything to repeat")]
+    InvalidRepetitionQuantifier,
+    #[error("unmatched {{")]
+    UnterminatedRepetition,
+    #[error("invalid content of {{...}}")]
+    InvalidRepetitionRange,
+    #[error("regular expression too big")]
+    CompiledTooBig,
 }
@@ -0,0 +1,157 @@
+use std::str;
+
+/// What the matchers read at a position: a char where the bytes there are valid UTF-8,
+/// otherwise a single byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Char(char),
+    Byte(u8),
+}
+
+impl Unit {
+    /// Number of bytes this unit takes in the input
+    pub fn len(self) -> usize {
+        match self {
+            Unit::Char(char) => char.len_utf8(),
+            Unit::Byte(_) => 1,
+        }
+    }
+}
+
+/// Input searched by the matchers, as bytes. Input from a `&str` always reads as chars,
+/// input given to `bytes::Regex` reads as bytes where it isn't valid UTF-8. Positions
+/// are byte offsets, and only ever fall between units.
+#[derive(Debug, Clone, Copy)]
+pub struct Input<'h> {
+    bytes: &'h [u8],
+}
+
+impl<'h> From<&'h str> for Input<'h> {
+    fn from(input: &'h str) -> Self {
+        Self {
+            bytes: input.as_bytes(),
+        }
+    }
+}
+
+impl<'h> From<&'h [u8]> for Input<'h> {
+    fn from(bytes: &'h [u8]) -> Self {
+        Self { bytes }
+    }
+}
+
+impl<'h> Input<'h> {
+    pub fn len(self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn as_bytes(self) -> &'h [u8] {
+        self.bytes
+    }
+
+    /// Unit starting at `position`, `None` at the end of the input
+    pub fn next_unit(self, position: usize) -> Option<Unit> {
+        let &first_byte = self.bytes.get(position)?;
+        if first_byte.is_ascii() {
+            return Some(Unit::Char(first_byte as char));
+        }
+
+        let width = match first_byte {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return Some(Unit::Byte(first_byte)),
+        };
+        let decoded = self
+            .bytes
+            .get(position..position + width)
+            .and_then(|sequence| str::from_utf8(sequence).ok())
+            .and_then(|sequence| sequence.chars().next());
+
+        Some(decoded.map_or(Unit::Byte(first_byte), Unit::Char))
+    }
+
+    /// Unit ending at `position`, `None` at the start of the input. A char can only start
+    /// where a unit starts, since its first byte can't be part of another char.
+    pub fn previous_unit(self, position: usize) -> Option<Unit> {
+        let &last_byte = self.bytes[..position].last()?;
+        if last_byte.is_ascii() {
+            return Some(Unit::Char(last_byte as char));
+        }
+
+        let char_ending_here =
+            (2..=position.min(4)).find_map(|width| match self.next_unit(position - width) {
+                Some(Unit::Char(char)) if char.len_utf8() == width => Some(Unit::Char(char)),
+                _ => None,
+            });
+
+        Some(char_ending_here.unwrap_or(Unit::Byte(last_byte)))
+    }
+
+    /// Every unit from `from` on, along with its position
+    pub fn units(self, from: usize) -> impl Iterator<Item = (usize, Unit)> + 'h {
+        let mut position = from;
+
+        std::iter::from_fn(move || {
+            let unit = self.next_unit(position)?;
+            let unit_position = position;
+            position += unit.len();
+
+            Some((unit_position, unit))
+        })
+    }
+}
+
+#[cfg(test)]
+mod input_tests {
+    use crate::regex::input::{Input, Unit};
+
+    #[test]
+    fn valid_utf8_reads_as_chars() {
+        let input = Input::from("tűz");
+
+        assert_eq!(
+            vec![
+                (0, Unit::Char('t')),
+                (1, Unit::Char('ű')),
+                (3, Unit::Char('z'))
+            ],
+            input.units(0).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(Unit::Char('ű')), input.previous_unit(3));
+    }
+
+    #[test]
+    fn invalid_bytes_read_as_bytes() {
+        let input = Input::from(&b"caf\xE9 \xC3\xA9\xF0\x9F"[..]);
+
+        assert_eq!(
+            vec![
+                (0, Unit::Char('c')),
+                (1, Unit::Char('a')),
+                (2, Unit::Char('f')),
+                (3, Unit::Byte(0xE9)),
+                (4, Unit::Char(' ')),
+                (5, Unit::Char('é')),
+                (7, Unit::Byte(0xF0)),
+                (8, Unit::Byte(0x9F)),
+            ],
+            input.units(0).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(Unit::Byte(0xE9)), input.previous_unit(4));
+        assert_eq!(Some(Unit::Char('é')), input.previous_unit(7));
+        assert_eq!(Some(Unit::Byte(0x9F)), input.previous_unit(9));
+    }
+
+    #[test]
+    fn chars_past_the_last_plane_read_as_bytes() {
+        let input = Input::from(&b"\xF4\x90\x80\x80"[..]);
+
+        assert_eq!(Some(Unit::Byte(0xF4)), input.next_unit(0));
+        assert_eq!(Some(Unit::Byte(0x80)), input.previous_unit(4));
+    }
+}
@@ -1,3 +1,15 @@
 pub mod pattern;
+#[allow(clippy::module_inception)]
 pub mod regex;
 pub mod error;
+pub mod nfa;
+pub mod pike_vm;
+pub mod dfa;
+pub mod builder;
+pub mod unicode;
+pub mod bytes;
+pub mod input;
+pub mod search;
+pub mod replace;
+pub mod backtrack;
+pub mod unicode_tables;
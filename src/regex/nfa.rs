@@ -0,0 +1,579 @@
+use crate::regex::pattern::{Pattern, Quantifier};
+use std::collections::VecDeque;
+
+/// Counted repetitions are unrolled, so nesting them multiplies their counts. Bigger
+/// NFAs are rejected rather than handed to the backtracking matcher, which could take
+/// exponential time on them. Searches take time proportional to the NFA size for every
+/// char, so this also keeps them reasonably fast.
+const MAX_INSTRUCTIONS: usize = 10_000;
+
+#[derive(Debug, PartialEq)]
+pub enum CompileError {
+    /// The patterns need backtracking, e.g. because they contain backreferences
+    Unsupported,
+    /// The NFA would have more than `MAX_INSTRUCTIONS`
+    TooBig,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Instruction {
+    /// Consumes one char matched by a single char pattern
+    Char(Pattern),
+    /// Forks the thread, the first target has priority over the second
+    Split(usize, usize),
+    Jump(usize),
+    /// Records the position in a slot, `2n` for the start of group `n` and `2n + 1` for
+    /// its end. Slots after those of the groups record where repetitions started.
+    Save(usize),
+    /// Ends a repetition of a pattern that can match empty. Like in the backtracking
+    /// matcher, jumps to `exit`, past the other repetitions, if it's empty, i.e. if it
+    /// started at the position saved in `slot`.
+    ExitIfEmpty {
+        slot: usize,
+        exit: usize,
+    },
+    AssertStartOfString,
+    AssertEndOfString,
+    AssertEndOfStringBeforeNewline,
+    AssertStartOfLine,
+    AssertEndOfLine,
+    /// Only ASCII chars are word chars if `ascii_only`, under the `-u` flag
+    AssertWordBoundary {
+        ascii_only: bool,
+    },
+    AssertNotWordBoundary {
+        ascii_only: bool,
+    },
+    AssertWordStartHalf {
+        ascii_only: bool,
+    },
+    AssertWordEndHalf {
+        ascii_only: bool,
+    },
+    Match,
+}
+
+/// Thompson NFA of a regex, simulated by the Pike VM in `pike_vm.rs`.
+#[derive(Debug, PartialEq)]
+pub struct Nfa {
+    pub instructions: Vec<Instruction>,
+    pub anchored_start: bool,
+    /// Number of groups, counting the implicit group 0 of the whole match
+    pub group_count: usize,
+    /// Number of slots `Save` records to, two per group and one per repetition that can
+    /// match empty
+    pub slot_count: usize,
+    /// Slots of the repetitions that can match empty each instruction is in, outermost
+    /// first. Whether they started at the current position decides where threads go.
+    pub repetition_slots: Vec<Vec<usize>>,
+}
+
+impl Nfa {
+    /// Fails with `CompileError::Unsupported` if the patterns can't be expressed as an
+    /// NFA, in which case the backtracking matcher has to be used. That's checked first,
+    /// so that the size of patterns the NFA is never used for doesn't matter.
+    pub fn compile(patterns: &VecDeque<Pattern>, group_count: usize) -> Result<Self, CompileError> {
+        if patterns
+            .iter()
+            .any(|pattern| pattern.contains(&needs_backtracking))
+        {
+            return Err(CompileError::Unsupported);
+        }
+
+        let mut compiler = Compiler {
+            instructions: Vec::new(),
+            slot_count: 2 * group_count,
+            repetition_slots: Vec::new(),
+            open_repetition_slots: Vec::new(),
+        };
+
+        compiler.compile_sequence(patterns.iter())?;
+        compiler.emit(Instruction::Match);
+
+        Ok(Self {
+            anchored_start: matches!(patterns.front(), Some(Pattern::StartOfString)),
+            instructions: compiler.instructions,
+            group_count,
+            slot_count: compiler.slot_count,
+            repetition_slots: compiler.repetition_slots,
+        })
+    }
+
+    /// DFA states don't know the previous char, which word boundaries and line anchors
+    /// depend on. They don't know the next one either, which `\Z` depends on.
+    pub fn is_dfa_compatible(&self) -> bool {
+        !self.instructions.iter().any(|instruction| {
+            matches!(
+                instruction,
+                Instruction::AssertWordBoundary { .. }
+                    | Instruction::AssertNotWordBoundary { .. }
+                    | Instruction::AssertWordStartHalf { .. }
+                    | Instruction::AssertWordEndHalf { .. }
+                    | Instruction::AssertStartOfLine
+                    | Instruction::AssertEndOfLine
+                    | Instruction::AssertEndOfStringBeforeNewline
+            )
+        })
+    }
+}
+
+/// Backtracking is what these control, the NFA never backtracks
+fn needs_backtracking(pattern: &Pattern) -> bool {
+    matches!(
+        pattern,
+        Pattern::Backreference(_)
+            | Pattern::CaseInsensitiveBackreference(_)
+            | Pattern::AsciiCaseInsensitiveBackreference(_)
+            | Pattern::AtomicGroup(_)
+            | Pattern::PossessiveQuantifier(_)
+            | Pattern::PositiveLookahead(_)
+            | Pattern::NegativeLookahead(_)
+            | Pattern::PositiveLookbehind(_)
+            | Pattern::NegativeLookbehind(_)
+    )
+}
+
+struct Compiler {
+    instructions: Vec<Instruction>,
+    slot_count: usize,
+    repetition_slots: Vec<Vec<usize>>,
+    /// Slots of the repetitions being compiled that can match empty
+    open_repetition_slots: Vec<usize>,
+}
+
+impl Compiler {
+    fn compile_sequence<'p>(
+        &mut self,
+        patterns: impl Iterator<Item = &'p Pattern>,
+    ) -> Result<(), CompileError> {
+        for pattern in patterns {
+            self.compile(pattern)?;
+        }
+
+        Ok(())
+    }
+
+    fn compile(&mut self, pattern: &Pattern) -> Result<(), CompileError> {
+        if let Some(quantifier) = pattern.quantifier() {
+            return self.compile_quantifier(quantifier);
+        }
+
+        match pattern {
+            Pattern::StartOfString => {
+                self.emit(Instruction::AssertStartOfString);
+            }
+
+            Pattern::EndOfString => {
+                self.emit(Instruction::AssertEndOfString);
+            }
+
+            Pattern::EndOfStringBeforeNewline => {
+                self.emit(Instruction::AssertEndOfStringBeforeNewline);
+            }
+
+            Pattern::StartOfLine => {
+                self.emit(Instruction::AssertStartOfLine);
+            }
+
+            Pattern::EndOfLine => {
+                self.emit(Instruction::AssertEndOfLine);
+            }
+
+            Pattern::WordBoundary | Pattern::AsciiWordBoundary => {
+                self.emit(Instruction::AssertWordBoundary {
+                    ascii_only: *pattern == Pattern::AsciiWordBoundary,
+                });
+            }
+
+            Pattern::NotWordBoundary | Pattern::AsciiNotWordBoundary => {
+                self.emit(Instruction::AssertNotWordBoundary {
+                    ascii_only: *pattern == Pattern::AsciiNotWordBoundary,
+                });
+            }
+
+            Pattern::WordStartHalf | Pattern::AsciiWordStartHalf => {
+                self.emit(Instruction::AssertWordStartHalf {
+                    ascii_only: *pattern == Pattern::AsciiWordStartHalf,
+                });
+            }
+
+            Pattern::WordEndHalf | Pattern::AsciiWordEndHalf => {
+                self.emit(Instruction::AssertWordEndHalf {
+                    ascii_only: *pattern == Pattern::AsciiWordEndHalf,
+                });
+            }
+
+            Pattern::Group(index, group) => {
+                self.emit(Instruction::Save(2 * index));
+                self.compile_sequence(group.iter())?;
+                self.emit(Instruction::Save(2 * index + 1));
+            }
+
+            Pattern::NonCapturingGroup(group) => {
+                self.compile_sequence(group.iter())?;
+            }
+
+            Pattern::Alternation(alternation) => {
+                let mut jumps_to_end = Vec::new();
+
+                for (i, variant) in alternation.iter().enumerate() {
+                    if i == alternation.len() - 1 {
+                        self.compile_sequence(variant.iter())?;
+                        break;
+                    }
+
+                    let split = self.emit(Instruction::Split(0, 0));
+                    self.compile_sequence(variant.iter())?;
+                    jumps_to_end.push(self.emit(Instruction::Jump(0)));
+                    self.patch_split(split, true);
+                }
+
+                for jump in jumps_to_end {
+                    self.instructions[jump] = Instruction::Jump(self.next_pc());
+                }
+            }
+
+            _ if needs_backtracking(pattern) => unreachable!("checked by Nfa::compile"),
+
+            _ => {
+                self.emit(Instruction::Char(pattern.clone()));
+            }
+        }
+
+        if self.instructions.len() > MAX_INSTRUCTIONS {
+            return Err(CompileError::TooBig);
+        }
+
+        Ok(())
+    }
+
+    /// Repetitions are unrolled: `min` mandatory copies, followed by either a loop
+    /// (unbounded) or `max - min` optional copies. When the pattern can match empty, the
+    /// copies from the `min`th on exit early on an empty repetition, like the backtracking
+    /// matcher does, so threads keep the priority it would give them.
+    fn compile_quantifier(&mut self, quantifier: Quantifier) -> Result<(), CompileError> {
+        let can_repeat_more = quantifier.max != Some(quantifier.min);
+        let slot = (can_repeat_more && quantifier.pattern.can_match_empty()).then(|| {
+            self.slot_count += 1;
+            self.slot_count - 1
+        });
+        let mut exits = Vec::new();
+
+        for count in 1..=quantifier.min {
+            if count == quantifier.min {
+                self.compile_repetition(quantifier.pattern, slot, &mut exits)?;
+            } else {
+                self.compile(quantifier.pattern)?;
+            }
+        }
+
+        match quantifier.max {
+            None => {
+                let split = self.emit(Instruction::Split(0, 0));
+                self.compile_repetition(quantifier.pattern, slot, &mut exits)?;
+                self.emit(Instruction::Jump(split));
+                self.patch_split(split, quantifier.greedy);
+            }
+
+            Some(max) => {
+                let mut splits = Vec::new();
+
+                for _ in quantifier.min..max {
+                    splits.push(self.emit(Instruction::Split(0, 0)));
+                    self.compile_repetition(quantifier.pattern, slot, &mut exits)?;
+                }
+
+                for split in splits {
+                    self.patch_split(split, quantifier.greedy);
+                }
+            }
+        }
+
+        let end = self.next_pc();
+        for pc in exits {
+            if let Instruction::ExitIfEmpty { exit, .. } = &mut self.instructions[pc] {
+                *exit = end;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compiles one repetition, saving where it starts in `slot` and checking whether
+    /// it's empty at its end if there's one. The checks are added to `exits` to be patched.
+    fn compile_repetition(
+        &mut self,
+        pattern: &Pattern,
+        slot: Option<usize>,
+        exits: &mut Vec<usize>,
+    ) -> Result<(), CompileError> {
+        let Some(slot) = slot else {
+            return self.compile(pattern);
+        };
+
+        self.emit(Instruction::Save(slot));
+        self.open_repetition_slots.push(slot);
+        self.compile(pattern)?;
+        exits.push(self.emit(Instruction::ExitIfEmpty { slot, exit: 0 }));
+        self.open_repetition_slots.pop();
+        Ok(())
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.instructions.push(instruction);
+        self.repetition_slots
+            .push(self.open_repetition_slots.clone());
+        self.instructions.len() - 1
+    }
+
+    fn next_pc(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Points a `Split` to the instructions right after it and to the next instruction to
+    /// be emitted, giving priority to the former when `prefer_first` is set.
+    fn patch_split(&mut self, split: usize, prefer_first: bool) {
+        let (first, skip) = (split + 1, self.next_pc());
+
+        self.instructions[split] = if prefer_first {
+            Instruction::Split(first, skip)
+        } else {
+            Instruction::Split(skip, first)
+        };
+    }
+}
+
+#[cfg(test)]
+mod compile_tests {
+    use crate::regex::nfa::Instruction::*;
+    use crate::regex::nfa::{CompileError, Nfa};
+    use crate::regex::pattern::Pattern::*;
+    use std::collections::VecDeque;
+
+    #[test]
+    fn char_literals() {
+        let nfa = Nfa::compile(&VecDeque::from([CharLiteral('a'), CharLiteral('b')]), 1).unwrap();
+
+        assert_eq!(
+            vec![Char(CharLiteral('a')), Char(CharLiteral('b')), Match],
+            nfa.instructions
+        );
+        assert!(!nfa.anchored_start);
+    }
+
+    #[test]
+    fn one_or_more_quantifier() {
+        let nfa = Nfa::compile(
+            &VecDeque::from([OneOrMoreQuantifier(Box::new(DigitClass))]),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Char(DigitClass),
+                Split(2, 4),
+                Char(DigitClass),
+                Jump(1),
+                Match
+            ],
+            nfa.instructions
+        );
+    }
+
+    #[test]
+    fn optional_quantifier() {
+        let nfa =
+            Nfa::compile(&VecDeque::from([OptionalQuantifier(Box::new(Wildcard))]), 1).unwrap();
+
+        assert_eq!(vec![Split(1, 2), Char(Wildcard), Match], nfa.instructions);
+    }
+
+    #[test]
+    fn capture_group_saves_its_span() {
+        let nfa = Nfa::compile(
+            &VecDeque::from([Group(1, VecDeque::from([CharLiteral('a')]))]),
+            2,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![Save(2), Char(CharLiteral('a')), Save(3), Match],
+            nfa.instructions
+        );
+    }
+
+    #[test]
+    fn bounded_repetition_is_unrolled() {
+        let nfa = Nfa::compile(
+            &VecDeque::from([Repetition {
+                pattern: Box::new(CharLiteral('a')),
+                min: 1,
+                max: Some(3),
+            }]),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Char(CharLiteral('a')),
+                Split(2, 5),
+                Char(CharLiteral('a')),
+                Split(4, 5),
+                Char(CharLiteral('a')),
+                Match
+            ],
+            nfa.instructions
+        );
+    }
+
+    #[test]
+    fn repetition_matching_empty_exits_when_empty() {
+        let nfa = Nfa::compile(
+            &VecDeque::from([ZeroOrMoreQuantifier(Box::new(OptionalQuantifier(
+                Box::new(CharLiteral('a')),
+            )))]),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Split(1, 6),
+                Save(2),
+                Split(3, 4),
+                Char(CharLiteral('a')),
+                ExitIfEmpty { slot: 2, exit: 6 },
+                Jump(0),
+                Match
+            ],
+            nfa.instructions
+        );
+        assert_eq!(vec![2], nfa.repetition_slots[3]);
+        assert_eq!(3, nfa.slot_count);
+    }
+
+    #[test]
+    fn lazy_quantifier_prefers_skipping() {
+        let nfa = Nfa::compile(
+            &VecDeque::from([LazyQuantifier(Box::new(ZeroOrMoreQuantifier(Box::new(
+                Wildcard,
+            ))))]),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![Split(3, 1), Char(Wildcard), Jump(0), Match],
+            nfa.instructions
+        );
+    }
+
+    #[test]
+    fn alternation() {
+        let nfa = Nfa::compile(
+            &VecDeque::from([Alternation(vec![
+                vec![CharLiteral('a')],
+                vec![CharLiteral('b')],
+                vec![CharLiteral('c')],
+            ])]),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                Split(1, 3),
+                Char(CharLiteral('a')),
+                Jump(7),
+                Split(4, 6),
+                Char(CharLiteral('b')),
+                Jump(7),
+                Char(CharLiteral('c')),
+                Match
+            ],
+            nfa.instructions
+        );
+    }
+
+    #[test]
+    fn start_and_end_of_string() {
+        let nfa = Nfa::compile(
+            &VecDeque::from([
+                StartOfString,
+                CharLiteral('a'),
+                CharLiteral('b'),
+                EndOfString,
+            ]),
+            1,
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![
+                AssertStartOfString,
+                Char(CharLiteral('a')),
+                Char(CharLiteral('b')),
+                AssertEndOfString,
+                Match
+            ],
+            nfa.instructions
+        );
+        assert!(nfa.anchored_start);
+    }
+
+    #[test]
+    fn word_boundaries_arent_dfa_compatible() {
+        let nfa = Nfa::compile(&VecDeque::from([WordBoundary, CharLiteral('a')]), 1).unwrap();
+
+        assert_eq!(
+            vec![
+                AssertWordBoundary { ascii_only: false },
+                Char(CharLiteral('a')),
+                Match
+            ],
+            nfa.instructions
+        );
+        assert!(!nfa.is_dfa_compatible());
+    }
+
+    #[test]
+    fn nested_repetitions_over_size_limit_are_too_big() {
+        let repeat = |pattern| Repetition {
+            pattern: Box::new(pattern),
+            min: 1000,
+            max: Some(1000),
+        };
+
+        assert_eq!(
+            Err(CompileError::TooBig),
+            Nfa::compile(&VecDeque::from([repeat(repeat(CharLiteral('a')))]), 1)
+        );
+    }
+
+    #[test]
+    fn backreference_is_unsupported_whatever_the_size() {
+        let repeat = |pattern| Repetition {
+            pattern: Box::new(pattern),
+            min: 1000,
+            max: Some(1000),
+        };
+        let group = Group(1, VecDeque::from([repeat(repeat(CharLiteral('a')))]));
+
+        assert_eq!(
+            Err(CompileError::Unsupported),
+            Nfa::compile(&VecDeque::from([group, Backreference(1)]), 2)
+        );
+    }
+
+    #[test]
+    fn backreference_isnt_compiled() {
+        let group = Group(1, VecDeque::from([CharLiteral('a')]));
+
+        assert_eq!(
+            Err(CompileError::Unsupported),
+            Nfa::compile(&VecDeque::from([group, Backreference(1)]), 2)
+        );
+    }
+}
@@ -1,226 +1,500 @@
+use crate::regex::input::{Input, Unit};
+use crate::regex::unicode::{
+    case_variants, is_decimal_digit, is_white_space, is_word_char, UnicodeProperty,
+};
 use std::cmp::PartialEq;
 use std::collections::VecDeque;
-use std::iter::{Enumerate, Peekable};
-use std::str::Chars;
 
-const ASCII_0: u8 = 48;
-const ASCII_9: u8 = 57;
-const ASCII_UPPERCASE_A: u8 = 65;
-const ASCII_UPPERCASE_Z: u8 = 90;
-const ASCII_LOWERCASE_A: u8 = 97;
-const ASCII_LOWERCASE_Z: u8 = 122;
+/// Start and end byte offset of every capture group, indexed by group number.
+/// Slot 0 is reserved for the whole match.
+pub type Captures = Vec<Option<(usize, usize)>>;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Pattern {
     CharLiteral(char),
+    /// `\xHH` from `\x80` on with Unicode off in a `bytes::Regex`, matches a byte that
+    /// isn't part of valid UTF-8
+    Byte(u8),
     DigitClass,
     AlphanumericClass,
-    PositiveCharGroup(Vec<char>),
-    NegativeCharGroup(Vec<char>),
-    StartOfString(Box<Pattern>),
-    EndOfString(Box<Pattern>),
+    WhitespaceClass,
+    NotDigitClass,
+    NotAlphanumericClass,
+    NotWhitespaceClass,
+    /// `\p{...}`
+    UnicodePropertyClass(UnicodeProperty),
+    /// `\P{...}`
+    NotUnicodePropertyClass(UnicodeProperty),
+    PositiveCharGroup(Vec<CharGroupItem>),
+    NegativeCharGroup(Vec<CharGroupItem>),
+    /// `^` or `\A`, zero-width, matches at the start of the input
+    StartOfString,
+    /// `$` or `\z`, zero-width, matches at the end of the input
+    EndOfString,
+    /// `\Z`, zero-width, matches at the end of the input or before a newline ending it
+    EndOfStringBeforeNewline,
     OneOrMoreQuantifier(Box<Pattern>),
     OptionalQuantifier(Box<Pattern>),
+    ZeroOrMoreQuantifier(Box<Pattern>),
+    Repetition {
+        pattern: Box<Pattern>,
+        min: usize,
+        max: Option<usize>,
+    },
+    /// `.`, any char but a newline
     Wildcard,
-    Group(VecDeque<Pattern>),
+    /// `.` with the `s` flag, any char including a newline
+    AnyChar,
+    /// `^` with the `m` flag, zero-width, matches at the start or after a newline
+    StartOfLine,
+    /// `$` with the `m` flag, zero-width, matches at the end or before a newline
+    EndOfLine,
+    /// Wraps a char literal or char group under the `i` flag, matching either case
+    CaseInsensitive(Box<Pattern>),
+    /// Like `CaseInsensitive`, but only ASCII letters match their other case
+    AsciiCaseInsensitive(Box<Pattern>),
+    WordBoundary,
+    NotWordBoundary,
+    /// `\b{start-half}`, zero-width, matches where no word char comes before
+    WordStartHalf,
+    /// `\b{end-half}`, zero-width, matches where no word char comes after
+    WordEndHalf,
+    /// Like `WordBoundary` under the `-u` flag, where only ASCII chars are word chars
+    AsciiWordBoundary,
+    AsciiNotWordBoundary,
+    AsciiWordStartHalf,
+    AsciiWordEndHalf,
+    Group(usize, VecDeque<Pattern>),
+    NonCapturingGroup(VecDeque<Pattern>),
     Alternation(Vec<Vec<Pattern>>),
+    /// Matches the text last captured by the group with this number. Fails when the group
+    /// didn't participate in the match (yet), e.g. `(a)?\1` against `b`.
     Backreference(usize),
+    /// `Backreference` under the `i` flag, matching the captured text in any case
+    CaseInsensitiveBackreference(usize),
+    /// Like `CaseInsensitiveBackreference`, but only ASCII letters match their other case
+    AsciiCaseInsensitiveBackreference(usize),
+    /// `\k<name>` while parsing, replaced by one of the backreferences once every group
+    /// name is known, since the group may come after it
+    NamedBackreference {
+        name: String,
+        case_insensitive: bool,
+        ascii_only: bool,
+    },
+    /// Wraps one of the quantifiers, making it repeat as few times as possible
+    LazyQuantifier(Box<Pattern>),
+    /// Wraps one of the quantifiers, making it never give back repetitions
+    PossessiveQuantifier(Box<Pattern>),
+    /// Matches like a sequence, but once it matched it's never backtracked into
+    AtomicGroup(VecDeque<Pattern>),
+    /// Zero-width, succeeds if the patterns match starting at the current position
+    PositiveLookahead(VecDeque<Pattern>),
+    /// Zero-width, succeeds if the patterns don't match starting at the current position
+    NegativeLookahead(VecDeque<Pattern>),
+    /// Zero-width, succeeds if the patterns match ending at the current position
+    PositiveLookbehind(VecDeque<Pattern>),
+    /// Zero-width, succeeds if the patterns don't match ending at the current position
+    NegativeLookbehind(VecDeque<Pattern>),
 }
 
-impl Pattern {
-    pub fn matches(
-        &self,
-        input: &mut Peekable<Enumerate<Chars>>,
-        next_pattern: Option<&Pattern>,
-        backreference_values: &mut Vec<String>,
-    ) -> bool {
+#[derive(Debug, PartialEq, Clone)]
+pub enum CharGroupItem {
+    Char(char),
+    /// Inclusive range of chars, e.g. `a-z`
+    Range(char, char),
+    /// Class embedded in brackets, e.g. `\d` in `[\d_]`
+    Class(Pattern),
+    /// Named class, e.g. `[:alpha:]` in `[[:alpha:]_]`
+    PosixClass(PosixClass),
+    /// Byte that isn't part of valid UTF-8, see `Pattern::Byte`
+    Byte(u8),
+    /// Inclusive range of such bytes
+    ByteRange(u8, u8),
+}
+
+/// Named classes of the POSIX "C" locale, which only contain ASCII chars
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PosixClass {
+    Alnum,
+    Alpha,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Xdigit,
+}
+
+impl PosixClass {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "alnum" => Some(PosixClass::Alnum),
+            "alpha" => Some(PosixClass::Alpha),
+            "blank" => Some(PosixClass::Blank),
+            "cntrl" => Some(PosixClass::Cntrl),
+            "digit" => Some(PosixClass::Digit),
+            "graph" => Some(PosixClass::Graph),
+            "lower" => Some(PosixClass::Lower),
+            "print" => Some(PosixClass::Print),
+            "punct" => Some(PosixClass::Punct),
+            "space" => Some(PosixClass::Space),
+            "upper" => Some(PosixClass::Upper),
+            "xdigit" => Some(PosixClass::Xdigit),
+            _ => None,
+        }
+    }
+
+    pub fn matches_char(&self, input_char: char) -> bool {
         match self {
-            Pattern::CharLiteral(char) => input
-                .next()
-                .is_some_and(|(_, next_char)| *char == next_char),
+            PosixClass::Alnum => input_char.is_ascii_alphanumeric(),
+            PosixClass::Alpha => input_char.is_ascii_alphabetic(),
+            PosixClass::Blank => input_char == ' ' || input_char == '\t',
+            PosixClass::Cntrl => input_char.is_ascii_control(),
+            PosixClass::Digit => input_char.is_ascii_digit(),
+            PosixClass::Graph => input_char.is_ascii_graphic(),
+            PosixClass::Lower => input_char.is_ascii_lowercase(),
+            PosixClass::Print => input_char.is_ascii_graphic() || input_char == ' ',
+            PosixClass::Punct => input_char.is_ascii_punctuation(),
+            // Unlike `char::is_ascii_whitespace`, POSIX includes the vertical tab
+            PosixClass::Space => input_char.is_ascii_whitespace() || input_char == '\x0b',
+            PosixClass::Upper => input_char.is_ascii_uppercase(),
+            PosixClass::Xdigit => input_char.is_ascii_hexdigit(),
+        }
+    }
+}
 
-            Pattern::DigitClass => input
-                .next()
-                .is_some_and(|(_, next_char)| is_char_digit(next_char)),
+impl CharGroupItem {
+    pub fn matches_char(&self, input_char: char) -> bool {
+        match self {
+            CharGroupItem::Char(char) => *char == input_char,
+            CharGroupItem::Range(start, end) => (*start..=*end).contains(&input_char),
+            CharGroupItem::Class(class) => class.matches_char(input_char),
+            CharGroupItem::PosixClass(posix_class) => posix_class.matches_char(input_char),
+            CharGroupItem::Byte(_) | CharGroupItem::ByteRange(..) => false,
+        }
+    }
 
-            Pattern::AlphanumericClass => input
-                .next()
-                .is_some_and(|(_, next_char)| is_char_alphanumeric(next_char)),
+    fn matches_byte(&self, byte: u8) -> bool {
+        match self {
+            CharGroupItem::Byte(item_byte) => *item_byte == byte,
+            CharGroupItem::ByteRange(start, end) => (*start..=*end).contains(&byte),
+            _ => false,
+        }
+    }
+}
 
-            Pattern::PositiveCharGroup(char_group) => input
-                .next()
-                .is_some_and(|(_, next_char)| char_group.contains(&next_char)),
+/// Any quantifier pattern, reduced to what is repeated and how often
+#[derive(Debug, Clone, Copy)]
+pub struct Quantifier<'p> {
+    pub pattern: &'p Pattern,
+    pub min: usize,
+    pub max: Option<usize>,
+    pub greedy: bool,
+}
 
-            Pattern::NegativeCharGroup(char_group) => input
-                .next()
-                .is_some_and(|(_, next_char)| !char_group.contains(&next_char)),
+impl Pattern {
+    /// Whether this pattern or any pattern nested in it satisfies `predicate`
+    pub fn contains(&self, predicate: &impl Fn(&Pattern) -> bool) -> bool {
+        if predicate(self) {
+            return true;
+        }
 
-            Pattern::StartOfString(start_pattern) => {
-                start_pattern.matches(input, next_pattern, backreference_values)
+        match self {
+            Pattern::OneOrMoreQuantifier(pattern)
+            | Pattern::OptionalQuantifier(pattern)
+            | Pattern::ZeroOrMoreQuantifier(pattern)
+            | Pattern::Repetition { pattern, .. }
+            | Pattern::LazyQuantifier(pattern)
+            | Pattern::PossessiveQuantifier(pattern) => pattern.contains(predicate),
+            Pattern::Group(_, group)
+            | Pattern::NonCapturingGroup(group)
+            | Pattern::AtomicGroup(group)
+            | Pattern::PositiveLookahead(group)
+            | Pattern::NegativeLookahead(group)
+            | Pattern::PositiveLookbehind(group)
+            | Pattern::NegativeLookbehind(group) => {
+                group.iter().any(|pattern| pattern.contains(predicate))
             }
+            Pattern::Alternation(alternation) => alternation
+                .iter()
+                .flatten()
+                .any(|pattern| pattern.contains(predicate)),
+            _ => false,
+        }
+    }
 
-            Pattern::EndOfString(end_pattern) => {
-                end_pattern.matches(input, next_pattern, backreference_values)
-                    && input.next().is_none()
-            }
+    /// Calls `visit` on this pattern, then on every pattern nested in it, stopping at the
+    /// first error
+    pub fn try_visit_mut<E>(
+        &mut self,
+        visit: &mut impl FnMut(&mut Pattern) -> Result<(), E>,
+    ) -> Result<(), E> {
+        visit(self)?;
 
-            Pattern::OneOrMoreQuantifier(current_pattern) => {
-                if !current_pattern.matches(input, next_pattern, backreference_values) {
-                    return false;
-                }
-
-                match next_pattern {
-                    // Advance input iterator
-                    None => while current_pattern.matches(input, None, backreference_values) {},
-
-                    Some(nxt_pattern) => {
-                        let input_clone = &mut input.clone();
-                        let mut to_advance = 0;
-
-                        if **current_pattern == *nxt_pattern {
-                            while input_clone.peek().is_some()
-                                && current_pattern.matches(input_clone, None, backreference_values)
-                            {
-                                to_advance += 1;
-                            }
-                            to_advance -= 1;
-                        } else {
-                            // Check next pattern instead of current in case current is Wildcard (".")
-                            while input_clone.peek().is_some()
-                                && !nxt_pattern.matches(input_clone, None, backreference_values)
-                            {
-                                to_advance += 1;
-                            }
-                        }
-
-                        for _ in 0..to_advance {
-                            input.next();
-                        }
-                    }
-                }
-
-                true
-            }
+        match self {
+            Pattern::OneOrMoreQuantifier(pattern)
+            | Pattern::OptionalQuantifier(pattern)
+            | Pattern::ZeroOrMoreQuantifier(pattern)
+            | Pattern::Repetition { pattern, .. }
+            | Pattern::LazyQuantifier(pattern)
+            | Pattern::PossessiveQuantifier(pattern) => pattern.try_visit_mut(visit),
+            Pattern::Group(_, group)
+            | Pattern::NonCapturingGroup(group)
+            | Pattern::AtomicGroup(group)
+            | Pattern::PositiveLookahead(group)
+            | Pattern::NegativeLookahead(group)
+            | Pattern::PositiveLookbehind(group)
+            | Pattern::NegativeLookbehind(group) => group
+                .iter_mut()
+                .try_for_each(|pattern| pattern.try_visit_mut(visit)),
+            Pattern::Alternation(alternation) => alternation
+                .iter_mut()
+                .flatten()
+                .try_for_each(|pattern| pattern.try_visit_mut(visit)),
+            _ => Ok(()),
+        }
+    }
 
-            Pattern::OptionalQuantifier(optional_pattern) => {
-                let next_char = input.peek();
-                if next_char.is_some() {
-                    match **optional_pattern {
-                        Pattern::CharLiteral(char_literal) => {
-                            if char_literal == next_char.unwrap().1 {
-                                input.next();
-                            }
-                        }
-                        _ => {
-                            optional_pattern.matches(input, None, backreference_values);
-                        }
-                    }
-                }
-
-                true
+    pub fn quantifier(&self) -> Option<Quantifier<'_>> {
+        let (pattern, min, max) = match self {
+            Pattern::OneOrMoreQuantifier(pattern) => (pattern, 1, None),
+            Pattern::ZeroOrMoreQuantifier(pattern) => (pattern, 0, None),
+            Pattern::OptionalQuantifier(pattern) => (pattern, 0, Some(1)),
+            Pattern::Repetition { pattern, min, max } => (pattern, *min, *max),
+            Pattern::LazyQuantifier(quantifier) => {
+                return quantifier.quantifier().map(|quantifier| Quantifier {
+                    greedy: false,
+                    ..quantifier
+                })
             }
+            _ => return None,
+        };
+
+        Some(Quantifier {
+            pattern,
+            min,
+            max,
+            greedy: true,
+        })
+    }
 
-            Pattern::Wildcard => input.next().is_some(),
-
-            Pattern::Group(group) => {
-                // For backreferences:
-                // save input, index and length before advancing/matching the group
-                // and add placeholder backref value
-                let mut input_clone = input.clone();
-                let (input_index_before, _) = input_clone.peek().unwrap_or_else(|| &(0usize, '\0'));
-                let backreferences_length = backreference_values.len();
-                backreference_values.push(String::new());
-
-                let mut group_patterns = group.iter().enumerate().peekable();
-                let mut next_group_pattern: Option<&Pattern> = None;
-
-                while let Some((i, group_pattern)) = group_patterns.next() {
-                    // No more patterns left, so we need the pattern outside the group
-                    if i == group.len() - 1 {
-                        next_group_pattern = next_pattern;
-                    } else {
-                        next_group_pattern = Some(group_patterns.peek().unwrap().1);
-                    }
-
-                    if !group_pattern.matches(input, next_group_pattern, backreference_values) {
-                        return false;
-                    }
-                }
-
-                // Capture the group's matched value
-                if let Some((input_index_after, _)) = input.peek() {
-                    let mut group_backreference = String::new();
-
-                    for _ in 0..(*input_index_after - *input_index_before) {
-                        group_backreference.push(input_clone.next().unwrap().1);
-                    }
-
-                    backreference_values.remove(backreferences_length);
-                    backreference_values.insert(backreferences_length, group_backreference);
-                }
-
-                true
-            }
+    /// Most chars this pattern can consume, `None` if there's no limit. Backreferences
+    /// have none, since what they match depends on the input.
+    pub fn max_width(&self) -> Option<usize> {
+        if self.is_single_char() {
+            return Some(1);
+        }
+
+        if let Some(quantifier) = self.quantifier() {
+            return match (quantifier.max, quantifier.pattern.max_width()) {
+                (Some(0), _) | (_, Some(0)) => Some(0),
+                (Some(max), Some(width)) => width.checked_mul(max),
+                _ => None,
+            };
+        }
+
+        match self {
+            Pattern::PossessiveQuantifier(pattern) => pattern.max_width(),
+            Pattern::Group(_, group)
+            | Pattern::NonCapturingGroup(group)
+            | Pattern::AtomicGroup(group) => max_sequence_width(group),
+            Pattern::Alternation(alternation) => alternation.iter().try_fold(0, |width, branch| {
+                Some(width.max(max_sequence_width(branch)?))
+            }),
+            Pattern::Backreference(_)
+            | Pattern::CaseInsensitiveBackreference(_)
+            | Pattern::AsciiCaseInsensitiveBackreference(_)
+            | Pattern::NamedBackreference { .. } => None,
+            // Anchors, word boundaries and lookarounds are zero-width
+            _ => Some(0),
+        }
+    }
 
-            Pattern::Alternation(alternation) => {
-                for variant in alternation {
-                    let input_clone = &mut input.clone();
-                    let variant_length = variant.len();
+    /// Whether this pattern can match without consuming any char
+    pub fn can_match_empty(&self) -> bool {
+        if self.is_single_char() {
+            return false;
+        }
 
-                    if variant
-                        .iter()
-                        .all(|pattern| pattern.matches(input_clone, None, backreference_values))
-                    {
-                        for _ in 0..variant_length {
-                            input.next();
-                        }
+        if let Some(quantifier) = self.quantifier() {
+            return quantifier.min == 0 || quantifier.pattern.can_match_empty();
+        }
 
-                        return true;
-                    }
-                }
+        match self {
+            Pattern::PossessiveQuantifier(pattern) => pattern.can_match_empty(),
+            Pattern::Group(_, group)
+            | Pattern::NonCapturingGroup(group)
+            | Pattern::AtomicGroup(group) => group.iter().all(Pattern::can_match_empty),
+            Pattern::Alternation(alternation) => alternation
+                .iter()
+                .any(|branch| branch.iter().all(Pattern::can_match_empty)),
+            // Anchors, word boundaries, lookarounds and backreferences, which may refer to
+            // an empty group
+            _ => true,
+        }
+    }
 
-                false
-            }
+    /// Whether this pattern always consumes exactly one char, or one byte outside valid
+    /// UTF-8, in which case it can be checked with `matches_unit` instead of going
+    /// through a continuation.
+    pub fn is_single_char(&self) -> bool {
+        matches!(
+            self,
+            Pattern::CharLiteral(_)
+                | Pattern::Byte(_)
+                | Pattern::DigitClass
+                | Pattern::AlphanumericClass
+                | Pattern::WhitespaceClass
+                | Pattern::NotDigitClass
+                | Pattern::NotAlphanumericClass
+                | Pattern::NotWhitespaceClass
+                | Pattern::UnicodePropertyClass(_)
+                | Pattern::NotUnicodePropertyClass(_)
+                | Pattern::PositiveCharGroup(_)
+                | Pattern::NegativeCharGroup(_)
+                | Pattern::Wildcard
+                | Pattern::AnyChar
+                | Pattern::CaseInsensitive(_)
+                | Pattern::AsciiCaseInsensitive(_)
+        )
+    }
 
-            Pattern::Backreference(nth) => {
-                let backreference = backreference_values.get(nth - 1).unwrap();
+    pub fn matches_unit(&self, unit: Unit) -> bool {
+        match unit {
+            Unit::Char(input_char) => self.matches_char(input_char),
+            Unit::Byte(byte) => self.matches_byte(byte),
+        }
+    }
+
+    /// Bytes outside valid UTF-8 aren't chars of any class, so they're only matched by
+    /// byte escapes and by patterns matching any char or excluding chars
+    fn matches_byte(&self, byte: u8) -> bool {
+        match self {
+            Pattern::Byte(pattern_byte) => *pattern_byte == byte,
+            Pattern::PositiveCharGroup(char_group) => {
+                char_group.iter().any(|item| item.matches_byte(byte))
+            }
+            Pattern::NegativeCharGroup(char_group) => {
+                !char_group.iter().any(|item| item.matches_byte(byte))
+            }
+            Pattern::CaseInsensitive(pattern) | Pattern::AsciiCaseInsensitive(pattern) => {
+                pattern.matches_byte(byte)
+            }
+            Pattern::NotDigitClass
+            | Pattern::NotAlphanumericClass
+            | Pattern::NotWhitespaceClass
+            | Pattern::NotUnicodePropertyClass(_)
+            | Pattern::Wildcard
+            | Pattern::AnyChar => true,
+            _ => false,
+        }
+    }
 
-                for backreference_char in backreference.chars() {
-                    if input
-                        .next()
-                        .is_none_or(|(_, input_char)| input_char != backreference_char)
-                    {
-                        return false;
-                    }
-                }
+    pub fn matches_char(&self, input_char: char) -> bool {
+        match self {
+            Pattern::CharLiteral(char) => *char == input_char,
+            Pattern::DigitClass => is_decimal_digit(input_char),
+            Pattern::AlphanumericClass => is_word_char(input_char),
+            Pattern::WhitespaceClass => is_white_space(input_char),
+            Pattern::NotDigitClass => !is_decimal_digit(input_char),
+            Pattern::NotAlphanumericClass => !is_word_char(input_char),
+            Pattern::NotWhitespaceClass => !is_white_space(input_char),
+            Pattern::UnicodePropertyClass(property) => property.matches_char(input_char),
+            Pattern::NotUnicodePropertyClass(property) => !property.matches_char(input_char),
+            Pattern::PositiveCharGroup(char_group) => {
+                char_group.iter().any(|item| item.matches_char(input_char))
+            }
+            Pattern::NegativeCharGroup(char_group) => {
+                !char_group.iter().any(|item| item.matches_char(input_char))
+            }
+            Pattern::Wildcard => input_char != '\n',
+            Pattern::AnyChar => true,
+            Pattern::CaseInsensitive(pattern) => match pattern.as_ref() {
+                Pattern::CharLiteral(char) => eq_ignore_case(*char, input_char),
+                pattern => pattern.matches_any_case(case_variants(input_char)),
+            },
+            Pattern::AsciiCaseInsensitive(pattern) => pattern.matches_any_case(
+                [
+                    input_char,
+                    input_char.to_ascii_lowercase(),
+                    input_char.to_ascii_uppercase(),
+                ]
+                .into_iter(),
+            ),
+            _ => false,
+        }
+    }
 
-                true
+    /// Whether the pattern matches any of `variants`, the forms of a char in every case
+    fn matches_any_case(&self, mut variants: impl Iterator<Item = char>) -> bool {
+        match self {
+            // A negated group has to reject every case, not accept any of them
+            Pattern::NegativeCharGroup(char_group) => {
+                !variants.any(|variant| char_group.iter().any(|item| item.matches_char(variant)))
             }
+            Pattern::NotUnicodePropertyClass(property) => {
+                !variants.any(|variant| property.matches_char(variant))
+            }
+            pattern => variants.any(|variant| pattern.matches_char(variant)),
         }
     }
 }
 
-#[inline(always)]
-fn is_char_alphanumeric(char: char) -> bool {
-    is_char_digit(char) || is_char_letter(char)
+/// Most chars a sequence of patterns can consume, `None` if there's no limit
+pub fn max_sequence_width<'p>(patterns: impl IntoIterator<Item = &'p Pattern>) -> Option<usize> {
+    patterns.into_iter().try_fold(0, |width: usize, pattern| {
+        width.checked_add(pattern.max_width()?)
+    })
+}
+
+/// Whether the chars are equal once case is ignored. Folding both sides also catches
+/// chars that no case mapping of the other one leads to, like `ſ` for `S`.
+pub fn eq_ignore_case(char: char, other: char) -> bool {
+    case_variants(char).any(|variant| case_variants(other).any(|other| other == variant))
+}
+
+pub fn is_end_before_newline(input: Input, position: usize) -> bool {
+    position == input.len() || (position + 1 == input.len() && input.as_bytes().ends_with(b"\n"))
+}
+
+pub fn is_start_of_line(input: Input, position: usize) -> bool {
+    position == 0 || input.as_bytes()[position - 1] == b'\n'
 }
 
-#[inline(always)]
-fn is_char_letter(char: char) -> bool {
-    is_char_uppercase_letter(char) || is_char_lowercase_letter(char)
+pub fn is_end_of_line(input: Input, position: usize) -> bool {
+    position == input.len() || input.as_bytes()[position] == b'\n'
 }
 
-#[inline(always)]
-fn is_char_uppercase_letter(char: char) -> bool {
-    char as u8 >= ASCII_UPPERCASE_A && char as u8 <= ASCII_UPPERCASE_Z
+/// Whether `position` is between a word char and a non-word char, the start and end of
+/// the input counting as non-word chars. Only ASCII chars are word chars if `ascii_only`.
+pub fn is_word_boundary(input: Input, position: usize, ascii_only: bool) -> bool {
+    is_word_start_half(input, position, ascii_only) != is_word_end_half(input, position, ascii_only)
 }
 
-#[inline(always)]
-fn is_char_lowercase_letter(char: char) -> bool {
-    char as u8 >= ASCII_LOWERCASE_A && char as u8 <= ASCII_LOWERCASE_Z
+/// Whether the char before `position`, if any, isn't a word char
+pub fn is_word_start_half(input: Input, position: usize, ascii_only: bool) -> bool {
+    !input
+        .previous_unit(position)
+        .is_some_and(|unit| is_word_unit(unit, ascii_only))
 }
 
-#[inline(always)]
-fn is_char_digit(char: char) -> bool {
-    char as u8 >= ASCII_0 && char as u8 <= ASCII_9
+/// Whether the char after `position`, if any, isn't a word char
+pub fn is_word_end_half(input: Input, position: usize, ascii_only: bool) -> bool {
+    !input
+        .next_unit(position)
+        .is_some_and(|unit| is_word_unit(unit, ascii_only))
+}
+
+fn is_word_unit(unit: Unit, ascii_only: bool) -> bool {
+    match unit {
+        Unit::Char(input_char) if ascii_only => {
+            input_char.is_ascii_alphanumeric() || input_char == '_'
+        }
+        Unit::Char(input_char) => is_word_char(input_char),
+        Unit::Byte(_) => false,
+    }
 }
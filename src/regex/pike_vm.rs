@@ -0,0 +1,392 @@
+use crate::regex::input::{Input, Unit};
+use crate::regex::nfa::{Instruction, Nfa};
+use crate::regex::pattern::{
+    is_end_before_newline, is_end_of_line, is_start_of_line, is_word_boundary, is_word_end_half,
+    is_word_start_half, Captures,
+};
+
+/// Runs all NFA threads in lockstep over the input, so every char is looked at once per
+/// instruction at most, giving O(n·m) matching regardless of the pattern.
+pub fn is_match(nfa: &Nfa, input: Input) -> bool {
+    let mut current = ThreadList::new(nfa.instructions.len());
+    let mut next = ThreadList::new(nfa.instructions.len());
+    let mut position = 0;
+
+    loop {
+        // Unanchored search: start a new thread at every position
+        if position == 0 || !nfa.anchored_start {
+            add_thread(nfa, &mut current, 0, position, input);
+        }
+
+        if current.matched {
+            return true;
+        }
+
+        let Some(input_unit) = input.next_unit(position) else {
+            return false;
+        };
+        let next_position = position + input_unit.len();
+
+        next.clear();
+        for &pc in &current.pcs {
+            if let Instruction::Char(pattern) = &nfa.instructions[pc] {
+                if pattern.matches_unit(input_unit) {
+                    add_thread(nfa, &mut next, pc + 1, next_position, input);
+                }
+            }
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        position = next_position;
+
+        if current.pcs.is_empty() && nfa.anchored_start {
+            return false;
+        }
+    }
+}
+
+/// Leftmost-first match starting at or after `from`, with the span of every group, in
+/// O(n·m).
+///
+/// Threads are kept in order of priority, the order in which backtracking would try
+/// them. A thread reaching `Match` wins over every thread after it, and no more threads
+/// are started, but the ones before it keep running since they'd have been tried first.
+pub fn search(nfa: &Nfa, input: Input, from: usize) -> Option<Captures> {
+    let mut search = CaptureSearch::new(nfa, input);
+    let mut current = CaptureThreadList::new(search.state_count, nfa.slot_count);
+    let mut next = CaptureThreadList::new(search.state_count, nfa.slot_count);
+    let mut slots = vec![None; nfa.slot_count];
+    let mut matched: Option<Vec<Option<usize>>> = None;
+    let mut position = from;
+
+    loop {
+        if matched.is_none() && (position == 0 || !nfa.anchored_start) {
+            slots.fill(None);
+            slots[0] = Some(position);
+            search.add_thread(&mut current, 0, position, &mut slots);
+        }
+
+        let input_unit = input.next_unit(position);
+        let next_position = position + input_unit.map_or(0, Unit::len);
+
+        next.clear();
+        for &(state, pc) in &current.threads {
+            match &nfa.instructions[pc] {
+                Instruction::Match => {
+                    let mut thread_slots = current.slots(state).to_vec();
+                    thread_slots[1] = Some(position);
+                    matched = Some(thread_slots);
+                    break;
+                }
+                Instruction::Char(pattern)
+                    if input_unit.is_some_and(|input_unit| pattern.matches_unit(input_unit)) =>
+                {
+                    slots.copy_from_slice(current.slots(state));
+                    search.add_thread(&mut next, pc + 1, next_position, &mut slots);
+                }
+                _ => {}
+            }
+        }
+
+        if input_unit.is_none() {
+            break;
+        }
+
+        std::mem::swap(&mut current, &mut next);
+        position = next_position;
+
+        if current.threads.is_empty() && (matched.is_some() || nfa.anchored_start) {
+            break;
+        }
+    }
+
+    let slots = matched?;
+    Some(
+        slots[..2 * nfa.group_count]
+            .chunks(2)
+            .map(|span| span[0].zip(span[1]))
+            .collect(),
+    )
+}
+
+/// Follows every jump, split and assertion from `pc`, adding the reached `Char`
+/// instructions to `threads`.
+fn add_thread(nfa: &Nfa, threads: &mut ThreadList, pc: usize, position: usize, input: Input) {
+    let mut stack = vec![pc];
+
+    while let Some(pc) = stack.pop() {
+        if !threads.insert(pc) {
+            continue;
+        }
+
+        match &nfa.instructions[pc] {
+            Instruction::Char(_) => {}
+            Instruction::Split(first, second) => {
+                stack.push(*second);
+                stack.push(*first);
+            }
+            Instruction::Jump(target) => stack.push(*target),
+            // Which repetitions exit early doesn't change where matches end
+            Instruction::Save(_) | Instruction::ExitIfEmpty { .. } => stack.push(pc + 1),
+            Instruction::AssertStartOfString => {
+                if position == 0 {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::AssertEndOfString => {
+                if position == input.len() {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::AssertEndOfStringBeforeNewline => {
+                if is_end_before_newline(input, position) {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::AssertStartOfLine => {
+                if is_start_of_line(input, position) {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::AssertEndOfLine => {
+                if is_end_of_line(input, position) {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::AssertWordBoundary { ascii_only } => {
+                if is_word_boundary(input, position, *ascii_only) {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::AssertNotWordBoundary { ascii_only } => {
+                if !is_word_boundary(input, position, *ascii_only) {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::AssertWordStartHalf { ascii_only } => {
+                if is_word_start_half(input, position, *ascii_only) {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::AssertWordEndHalf { ascii_only } => {
+                if is_word_end_half(input, position, *ascii_only) {
+                    stack.push(pc + 1);
+                }
+            }
+            Instruction::Match => threads.matched = true,
+        }
+    }
+}
+
+enum Frame {
+    Explore(usize),
+    /// Puts back the value a `Save` overwrote, once the threads after it are added
+    RestoreSlot(usize, Option<usize>),
+}
+
+/// Follows threads like `add_thread`, but in order of priority and recording their slots.
+///
+/// Two threads at the same instruction and position only go the same way if the same
+/// repetitions that can match empty started at that position, since an empty repetition
+/// exits early. Those a thread is in started in order, so it's in one of `n + 1` states
+/// for an instruction in `n` of them, depending on how many didn't start there.
+struct CaptureSearch<'n, 'h> {
+    nfa: &'n Nfa,
+    input: Input<'h>,
+    /// State of every instruction where none of its repetitions started before
+    first_states: Vec<usize>,
+    state_count: usize,
+    stack: Vec<Frame>,
+}
+
+impl<'n, 'h> CaptureSearch<'n, 'h> {
+    fn new(nfa: &'n Nfa, input: Input<'h>) -> Self {
+        let mut first_states = Vec::with_capacity(nfa.instructions.len());
+        let mut state_count = 0;
+
+        for slots in &nfa.repetition_slots {
+            first_states.push(state_count);
+            state_count += slots.len() + 1;
+        }
+
+        Self {
+            nfa,
+            input,
+            first_states,
+            state_count,
+            stack: Vec::new(),
+        }
+    }
+
+    fn state(&self, pc: usize, position: usize, slots: &[Option<usize>]) -> usize {
+        let started_before = self.nfa.repetition_slots[pc]
+            .iter()
+            .take_while(|&&slot| slots[slot] != Some(position))
+            .count();
+
+        self.first_states[pc] + started_before
+    }
+
+    /// Adds the threads reached from `pc` to `threads`, `slots` holding those of the
+    /// thread being followed
+    fn add_thread(
+        &mut self,
+        threads: &mut CaptureThreadList,
+        pc: usize,
+        position: usize,
+        slots: &mut [Option<usize>],
+    ) {
+        let input = self.input;
+        self.stack.push(Frame::Explore(pc));
+
+        while let Some(frame) = self.stack.pop() {
+            let pc = match frame {
+                Frame::Explore(pc) => pc,
+                Frame::RestoreSlot(slot, value) => {
+                    slots[slot] = value;
+                    continue;
+                }
+            };
+
+            let state = self.state(pc, position, slots);
+            if !threads.insert(state, pc) {
+                continue;
+            }
+
+            let is_passed = match &self.nfa.instructions[pc] {
+                Instruction::Char(_) | Instruction::Match => {
+                    threads.set_slots(state, slots);
+                    false
+                }
+                Instruction::Split(first, second) => {
+                    self.stack.push(Frame::Explore(*second));
+                    self.stack.push(Frame::Explore(*first));
+                    false
+                }
+                Instruction::Jump(target) => {
+                    self.stack.push(Frame::Explore(*target));
+                    false
+                }
+                Instruction::Save(slot) => {
+                    self.stack.push(Frame::RestoreSlot(*slot, slots[*slot]));
+                    slots[*slot] = Some(position);
+                    true
+                }
+                Instruction::ExitIfEmpty { slot, exit } => {
+                    let target = if slots[*slot] == Some(position) {
+                        *exit
+                    } else {
+                        pc + 1
+                    };
+                    self.stack.push(Frame::Explore(target));
+                    false
+                }
+                Instruction::AssertStartOfString => position == 0,
+                Instruction::AssertEndOfString => position == input.len(),
+                Instruction::AssertEndOfStringBeforeNewline => {
+                    is_end_before_newline(input, position)
+                }
+                Instruction::AssertStartOfLine => is_start_of_line(input, position),
+                Instruction::AssertEndOfLine => is_end_of_line(input, position),
+                Instruction::AssertWordBoundary { ascii_only } => {
+                    is_word_boundary(input, position, *ascii_only)
+                }
+                Instruction::AssertNotWordBoundary { ascii_only } => {
+                    !is_word_boundary(input, position, *ascii_only)
+                }
+                Instruction::AssertWordStartHalf { ascii_only } => {
+                    is_word_start_half(input, position, *ascii_only)
+                }
+                Instruction::AssertWordEndHalf { ascii_only } => {
+                    is_word_end_half(input, position, *ascii_only)
+                }
+            };
+
+            if is_passed {
+                self.stack.push(Frame::Explore(pc + 1));
+            }
+        }
+    }
+}
+
+struct ThreadList {
+    pcs: Vec<usize>,
+    contains: Vec<bool>,
+    matched: bool,
+}
+
+impl ThreadList {
+    fn new(size: usize) -> Self {
+        Self {
+            pcs: Vec::with_capacity(size),
+            contains: vec![false; size],
+            matched: false,
+        }
+    }
+
+    fn insert(&mut self, pc: usize) -> bool {
+        if self.contains[pc] {
+            return false;
+        }
+
+        self.contains[pc] = true;
+        self.pcs.push(pc);
+        true
+    }
+
+    fn clear(&mut self) {
+        for &pc in &self.pcs {
+            self.contains[pc] = false;
+        }
+
+        self.pcs.clear();
+        self.matched = false;
+    }
+}
+
+/// Threads by state, see `CaptureSearch`, along with their instruction and slots, kept
+/// in order of priority
+struct CaptureThreadList {
+    threads: Vec<(usize, usize)>,
+    contains: Vec<bool>,
+    /// `slot_count` slots per state
+    slots: Vec<Option<usize>>,
+    slot_count: usize,
+}
+
+impl CaptureThreadList {
+    fn new(state_count: usize, slot_count: usize) -> Self {
+        Self {
+            threads: Vec::with_capacity(state_count),
+            contains: vec![false; state_count],
+            slots: vec![None; state_count * slot_count],
+            slot_count,
+        }
+    }
+
+    fn insert(&mut self, state: usize, pc: usize) -> bool {
+        if self.contains[state] {
+            return false;
+        }
+
+        self.contains[state] = true;
+        self.threads.push((state, pc));
+        true
+    }
+
+    fn slots(&self, state: usize) -> &[Option<usize>] {
+        &self.slots[state * self.slot_count..(state + 1) * self.slot_count]
+    }
+
+    fn set_slots(&mut self, state: usize, slots: &[Option<usize>]) {
+        self.slots[state * self.slot_count..(state + 1) * self.slot_count].copy_from_slice(slots);
+    }
+
+    fn clear(&mut self) {
+        for &(state, _) in &self.threads {
+            self.contains[state] = false;
+        }
+
+        self.threads.clear();
+    }
+}
@@ -1,71 +1,265 @@
+use crate::regex::backtrack;
+use crate::regex::builder::{RegexBuilder, RegexOptions};
+use crate::regex::dfa::LazyDfa;
 use crate::regex::error::RegexParsingError;
 use crate::regex::error::RegexParsingError::*;
-use crate::regex::pattern::Pattern;
+use crate::regex::input::Input;
+use crate::regex::nfa::{CompileError, Nfa};
 use crate::regex::pattern::Pattern::*;
+use crate::regex::pattern::{max_sequence_width, Captures, CharGroupItem, Pattern, PosixClass};
+use crate::regex::pike_vm;
+use crate::regex::replace::Replacer;
+use crate::regex::search;
+use crate::regex::search::{CaptureMatches, Match, Matches, Searches, Split, SplitN};
+use crate::regex::unicode::{is_cased, UnicodeProperty};
+use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::iter::Peekable;
 use std::str::Chars;
+use std::sync::{Arc, Mutex};
+
+const MAX_REPETITION_COUNT: usize = 1000;
+const MAX_CODE_POINT_DIGITS: usize = 6;
+
+/// Inline flags, set with `(?flags)` for the rest of the enclosing group or with
+/// `(?flags:...)` for a group of their own, and the regex options affecting parsing
+#[derive(Debug, Default, Clone, Copy)]
+struct Flags {
+    /// `i`, letters match both their lowercase and uppercase forms
+    case_insensitive: bool,
+    /// `m`, `^` and `$` also match at the start and end of every line
+    multi_line: bool,
+    /// `s`, `.` also matches a newline
+    dot_matches_new_line: bool,
+    /// `x`, whitespace is ignored and `#` starts a comment running to the end of the line
+    extended: bool,
+    /// `-u`, `\w`, `\d`, `\s` and case insensitivity only consider ASCII chars
+    ascii_only: bool,
+    /// Parsing for `bytes::Regex`, where `\x80` to `\xFF` stand for raw bytes rather than
+    /// chars when Unicode is off
+    raw_bytes: bool,
+}
 
 pub struct Regex {
     patterns: VecDeque<Pattern>,
+    /// Name of every capture group, by group number, starting with the whole match
+    capture_names: Arc<[Option<String>]>,
+    nfa: Option<Nfa>,
+    dfa: Option<Mutex<LazyDfa>>,
 }
 
 impl Regex {
     pub fn new(pattern: &str) -> Result<Self, RegexParsingError> {
+        RegexBuilder::new(pattern).build()
+    }
+
+    pub(crate) fn with_options(
+        pattern: &str,
+        options: RegexOptions,
+    ) -> Result<Self, RegexParsingError> {
         if pattern.is_empty() {
             return Err(EmptyRegex);
         }
 
+        let mut capture_names = vec![None];
+        let mut patterns = Self::parse_pattern(
+            &mut pattern.chars().peekable(),
+            &mut capture_names,
+            Flags {
+                raw_bytes: options.raw_bytes,
+                ..Flags::default()
+            },
+            false,
+        )?;
+
+        // Resolved and checked once every group is known, since backreferences may come
+        // before their group
+        patterns
+            .iter_mut()
+            .try_for_each(|pattern| Self::resolve_group_names(pattern, &capture_names))?;
+
+        let is_undefined_backreference = |pattern: &Pattern| matches!(pattern, Backreference(index) | CaseInsensitiveBackreference(index) | AsciiCaseInsensitiveBackreference(index) if *index >= capture_names.len());
+        if patterns
+            .iter()
+            .any(|pattern| pattern.contains(&is_undefined_backreference))
+        {
+            return Err(UndefinedGroupNumber);
+        }
+
+        let nfa = match Nfa::compile(&patterns, capture_names.len()) {
+            Ok(nfa) => Some(nfa),
+            Err(CompileError::Unsupported) => None,
+            Err(CompileError::TooBig) => return Err(CompiledTooBig),
+        };
+        let dfa = nfa
+            .as_ref()
+            .filter(|nfa| nfa.is_dfa_compatible())
+            .map(|_| Mutex::new(LazyDfa::new(options.dfa_size_limit)));
+
         Ok(Self {
-            patterns: Self::parse_pattern(&mut pattern.chars().peekable())?,
+            nfa,
+            dfa,
+            patterns,
+            capture_names: capture_names.into(),
         })
     }
 
     pub fn matches(&self, input: &str) -> bool {
-        let start_indexes = self.find_start_indexes(input);
+        self.is_match(input.into())
+    }
 
-        for index in start_indexes {
-            let mut matches_at_index = true;
+    pub(crate) fn is_match(&self, input: Input) -> bool {
+        if let Some(nfa) = &self.nfa {
+            // The DFA cache is shared, so a concurrent search uses the Pike VM instead of
+            // waiting for it
+            if let Some(Ok(mut dfa)) = self.dfa.as_ref().map(Mutex::try_lock) {
+                if let Some(is_match) = dfa.is_match(nfa, input) {
+                    return is_match;
+                }
+            }
 
-            let input_chars = &mut input[index..].chars().enumerate().peekable();
-            let mut patterns = self.patterns.iter().peekable();
-            let mut backreference_values: Vec<String> = Vec::new();
+            return pike_vm::is_match(nfa, input);
+        }
 
-            while let Some(pattern) = patterns.next() {
-                let next_pattern: Option<&Pattern> = patterns.peek().map(|&val| val);
+        self.search_at(input, 0).is_some()
+    }
 
-                if !pattern.matches(input_chars, next_pattern, &mut backreference_values) {
-                    matches_at_index = false;
-                    break;
-                }
-            }
+    /// Leftmost match in `input`. Alternatives and quantifiers are tried in order of
+    /// preference and the first one leading to a match wins, rather than the longest one.
+    pub fn find<'h>(&self, input: &'h str) -> Option<Match<'h>> {
+        let captures = self.search_at(input.into(), 0)?;
+        let (start, end) = captures[0]?;
 
-            if matches_at_index {
-                return true;
-            }
+        Some(Match::new(input, start, end))
+    }
+
+    /// Every non-overlapping match in `input`, from left to right. An empty match right
+    /// where the previous match ended is skipped.
+    pub fn find_iter<'r, 'h>(&'r self, input: &'h str) -> Matches<'r, 'h> {
+        Matches::new(self, input)
+    }
+
+    /// Leftmost match in `input` like `find`, along with what every group matched
+    pub fn captures<'h>(&self, input: &'h str) -> Option<search::Captures<'h>> {
+        let spans = self.search_at(input.into(), 0)?;
+
+        Some(search::Captures::new(
+            input,
+            spans,
+            self.capture_names.clone(),
+        ))
+    }
+
+    /// Groups of every non-overlapping match in `input`, like `find_iter`
+    pub fn captures_iter<'r, 'h>(&'r self, input: &'h str) -> CaptureMatches<'r, 'h> {
+        CaptureMatches::new(Searches::new(self, input), self.capture_names.clone())
+    }
+
+    /// Text of `input` between the matches, including the text before the first match and
+    /// after the last one, which are empty when a match is at the start or the end
+    pub fn split<'r, 'h>(&'r self, input: &'h str) -> Split<'r, 'h> {
+        Split::new(self, input)
+    }
+
+    /// Like `split`, but returns at most `limit` pieces, the last one being everything
+    /// after the match before it
+    pub fn splitn<'r, 'h>(&'r self, input: &'h str, limit: usize) -> SplitN<'r, 'h> {
+        SplitN::new(self, input, limit)
+    }
+
+    /// Replaces the leftmost match in `input`, see `replacen`
+    pub fn replace<'h>(&self, input: &'h str, replacer: impl Replacer) -> Cow<'h, str> {
+        self.replacen(input, 1, replacer)
+    }
+
+    /// Replaces every non-overlapping match in `input`, see `replacen`
+    pub fn replace_all<'h>(&self, input: &'h str, replacer: impl Replacer) -> Cow<'h, str> {
+        self.replacen(input, 0, replacer)
+    }
+
+    /// Replaces the first `limit` non-overlapping matches in `input`, or all of them if
+    /// `limit` is 0. A string replacement can refer to groups with `$1`, `${1}`, `$name`
+    /// or `${name}`, and `$$` stands for a `$`. Returns `input` untouched if nothing
+    /// matched.
+    pub fn replacen<'h>(
+        &self,
+        input: &'h str,
+        limit: usize,
+        mut replacer: impl Replacer,
+    ) -> Cow<'h, str> {
+        let limit = if limit == 0 { usize::MAX } else { limit };
+        let mut captures_iter = self.captures_iter(input).take(limit).peekable();
+        if captures_iter.peek().is_none() {
+            return Cow::Borrowed(input);
         }
 
-        false
+        let mut result = String::with_capacity(input.len());
+        let mut last_match_end = 0;
+
+        for captures in captures_iter {
+            let whole_match = captures.get(0).expect("group 0 is the whole match");
+
+            result.push_str(&input[last_match_end..whole_match.start()]);
+            replacer.replace_append(&captures, &mut result);
+            last_match_end = whole_match.end();
+        }
+
+        result.push_str(&input[last_match_end..]);
+        Cow::Owned(result)
+    }
+
+    /// Number of groups, counting the implicit group 0 of the whole match
+    pub fn captures_len(&self) -> usize {
+        self.capture_names.len()
+    }
+
+    /// Name of every group by group number, `None` for group 0 and unnamed groups
+    pub fn capture_names(&self) -> impl Iterator<Item = Option<&str>> {
+        self.capture_names.iter().map(|name| name.as_deref())
     }
 
+    /// Parses up to the end of `pattern`, or up to and including the `)` closing the
+    /// group when `in_group`
     fn parse_pattern(
         pattern: &mut Peekable<Chars>,
+        capture_names: &mut Vec<Option<String>>,
+        mut flags: Flags,
+        in_group: bool,
     ) -> Result<VecDeque<Pattern>, RegexParsingError> {
         let mut result = VecDeque::new();
-        let mut pattern_starts_with = false;
+        let mut alternation_branches: Vec<Vec<Pattern>> = Vec::new();
+        let mut is_closed = false;
 
         while let Some(current_char) = pattern.next() {
             match current_char {
-                '^' => {
-                    pattern_starts_with = true;
-                    if pattern.peek().is_none() {
-                        return Err(InvalidStart);
+                whitespace if flags.extended && whitespace.is_whitespace() => {}
+
+                '#' if flags.extended => {
+                    for comment_char in pattern.by_ref() {
+                        if comment_char == '\n' {
+                            break;
+                        }
                     }
                 }
 
+                '^' if flags.multi_line => {
+                    result.push_back(StartOfLine);
+                }
+
+                '$' if flags.multi_line => {
+                    result.push_back(EndOfLine);
+                }
+
+                '^' => {
+                    result.push_back(StartOfString);
+                }
+
                 '$' => {
-                    let previous_pattern = result.pop_back().ok_or(InvalidEnd)?;
-                    result.push_back(EndOfString(Box::new(previous_pattern)));
+                    result.push_back(EndOfString);
+                }
+
+                '.' if flags.dot_matches_new_line => {
+                    result.push_back(AnyChar);
                 }
 
                 '.' => {
@@ -73,121 +267,728 @@ impl Regex {
                 }
 
                 '\\' => match pattern.next().ok_or(InvalidCharClass)? {
-                    'w' => result.push_back(AlphanumericClass),
-                    'd' => result.push_back(DigitClass),
-                    '\\' => result.push_back(CharLiteral('\\')),
-                    number => result.push_back(Backreference(
-                        number.to_digit(10).ok_or(InvalidBackreference)? as usize,
+                    class @ ('w' | 'd' | 's' | 'W' | 'D' | 'S') => {
+                        result.push_back(Self::parse_class(class, flags))
+                    }
+                    'p' => result.push_back(Self::apply_case_flag(
+                        UnicodePropertyClass(Self::parse_unicode_property(pattern)?),
+                        flags,
+                    )),
+                    'P' => result.push_back(Self::apply_case_flag(
+                        NotUnicodePropertyClass(Self::parse_unicode_property(pattern)?),
+                        flags,
                     )),
+                    'b' if pattern.peek() == Some(&'{')
+                        && pattern
+                            .clone()
+                            .nth(1)
+                            .is_some_and(|next_char| next_char.is_ascii_alphabetic()) =>
+                    {
+                        result.push_back(Self::parse_word_boundary_kind(pattern, flags)?)
+                    }
+                    'b' if flags.ascii_only => result.push_back(AsciiWordBoundary),
+                    'B' if flags.ascii_only => result.push_back(AsciiNotWordBoundary),
+                    'b' => result.push_back(WordBoundary),
+                    'B' => result.push_back(NotWordBoundary),
+                    'A' => result.push_back(StartOfString),
+                    'z' => result.push_back(EndOfString),
+                    'Z' => result.push_back(EndOfStringBeforeNewline),
+                    'k' if pattern.next_if_eq(&'<').is_some() => {
+                        result.push_back(NamedBackreference {
+                            name: Self::parse_group_name(pattern, '>')?,
+                            case_insensitive: flags.case_insensitive,
+                            ascii_only: flags.ascii_only,
+                        });
+                    }
+                    number if number.is_ascii_digit() => {
+                        result.push_back(Self::parse_backreference(number, pattern, flags)?)
+                    }
+                    escaped_char => {
+                        match Self::parse_literal_escape(escaped_char, pattern, flags)? {
+                            Some(CharGroupItem::Byte(byte)) => result.push_back(Byte(byte)),
+                            Some(CharGroupItem::Char(char)) => {
+                                result.push_back(Self::apply_case_flag(CharLiteral(char), flags))
+                            }
+                            _ => return Err(InvalidEscape),
+                        }
+                    }
                 },
 
                 '?' => {
                     let previous_pattern = result.pop_back().ok_or(InvalidOptionalQuantifier)?;
-                    result.push_back(OptionalQuantifier(Box::new(previous_pattern)))
+                    Self::push_quantifier(
+                        &mut result,
+                        pattern,
+                        OptionalQuantifier(Box::new(previous_pattern)),
+                    );
                 }
 
                 '+' => {
                     let previous_pattern = result.pop_back().ok_or(InvalidOneOrMoreQuantifier)?;
-                    result.push_back(OneOrMoreQuantifier(Box::new(previous_pattern)))
+                    Self::push_quantifier(
+                        &mut result,
+                        pattern,
+                        OneOrMoreQuantifier(Box::new(previous_pattern)),
+                    );
+                }
+
+                '*' => {
+                    let previous_pattern = result.pop_back().ok_or(InvalidZeroOrMoreQuantifier)?;
+                    Self::push_quantifier(
+                        &mut result,
+                        pattern,
+                        ZeroOrMoreQuantifier(Box::new(previous_pattern)),
+                    );
+                }
+
+                // A brace that doesn't start a count is a literal, e.g. in `{"key": 1}`
+                '{' if pattern
+                    .peek()
+                    .is_some_and(|next_char| next_char.is_ascii_digit()) =>
+                {
+                    let (min, max) = Self::parse_repetition_bounds(pattern)?;
+                    let previous_pattern = result.pop_back().ok_or(InvalidRepetitionQuantifier)?;
+
+                    Self::push_quantifier(
+                        &mut result,
+                        pattern,
+                        Repetition {
+                            pattern: Box::new(previous_pattern),
+                            min,
+                            max,
+                        },
+                    );
                 }
 
                 '[' => {
-                    let mut group_chars: Vec<char> = Vec::new();
-                    let mut is_positive_group = true;
-
-                    while let Some(next_char) = pattern.next() {
-                        match next_char {
-                            ']' => break,
-                            '^' => is_positive_group = false,
-                            _ => group_chars.push(next_char),
-                        }
-                    }
+                    result.push_back(Self::apply_case_flag(
+                        Self::parse_char_group(pattern, flags)?,
+                        flags,
+                    ));
+                }
 
-                    if is_positive_group {
-                        result.push_back(PositiveCharGroup(group_chars))
-                    } else {
-                        result.push_back(NegativeCharGroup(group_chars))
+                '(' => {
+                    if let Some(group) = Self::parse_group(pattern, capture_names, &mut flags)? {
+                        result.push_back(group);
                     }
                 }
 
-                '(' => {
-                    result.push_back(Group(Self::parse_pattern(pattern)?));
+                ')' if in_group => {
+                    is_closed = true;
+                    break;
                 }
 
-                ')' => return Ok(result),
+                ')' => return Err(UnmatchedClosingParenthesis),
 
                 '|' => {
-                    let mut alternation_candidates = Vec::new();
-                    let mut current_candidate = Vec::new();
+                    alternation_branches.push(result.into());
+                    result = VecDeque::new();
+                }
 
-                    while let Some(char_literal) = result.pop_front() {
-                        current_candidate.push(char_literal);
-                    }
+                _ => {
+                    result.push_back(Self::apply_case_flag(CharLiteral(current_char), flags));
+                }
+            }
+        }
 
-                    alternation_candidates.push(current_candidate);
+        if in_group && !is_closed {
+            return Err(UnterminatedGroup);
+        }
 
-                    current_candidate = Vec::new();
-                    while let Some(next_pattern) = pattern.next() {
-                        match next_pattern {
-                            '|' => {
-                                alternation_candidates.push(current_candidate);
-                                current_candidate = Vec::new();
-                            }
+        if alternation_branches.is_empty() {
+            return Ok(result);
+        }
 
-                            ')' => {
-                                alternation_candidates.push(current_candidate);
-                                result.push_back(Alternation(alternation_candidates));
-                                return Ok(result);
-                            }
+        alternation_branches.push(result.into());
+        Ok(VecDeque::from([Alternation(alternation_branches)]))
+    }
 
-                            next_char => current_candidate.push(CharLiteral(next_char)),
-                        }
+    /// Wraps char literals and char groups so they match both cases under the `i` flag.
+    /// Literals without a case, like digits, are left as they are.
+    fn apply_case_flag(pattern: Pattern, flags: Flags) -> Pattern {
+        let has_case = match &pattern {
+            CharLiteral(char) if flags.ascii_only => char.is_ascii_alphabetic(),
+            CharLiteral(char) => is_cased(*char),
+            Byte(_) => false,
+            _ => true,
+        };
+
+        match (flags.case_insensitive && has_case, flags.ascii_only) {
+            (false, _) => pattern,
+            (true, false) => CaseInsensitive(Box::new(pattern)),
+            (true, true) => AsciiCaseInsensitive(Box::new(pattern)),
+        }
+    }
+
+    /// Pattern for one of the `\w`, `\d` and `\s` classes or their negation. Without
+    /// Unicode, they're char groups of their ASCII chars instead.
+    fn parse_class(class: char, flags: Flags) -> Pattern {
+        if flags.ascii_only {
+            let items = match class.to_ascii_lowercase() {
+                'w' => vec![
+                    CharGroupItem::PosixClass(PosixClass::Alnum),
+                    CharGroupItem::Char('_'),
+                ],
+                'd' => vec![CharGroupItem::PosixClass(PosixClass::Digit)],
+                _ => vec![CharGroupItem::PosixClass(PosixClass::Space)],
+            };
+
+            return if class.is_ascii_lowercase() {
+                PositiveCharGroup(items)
+            } else {
+                NegativeCharGroup(items)
+            };
+        }
+
+        match class {
+            'w' => AlphanumericClass,
+            'd' => DigitClass,
+            's' => WhitespaceClass,
+            'W' => NotAlphanumericClass,
+            'D' => NotDigitClass,
+            _ => NotWhitespaceClass,
+        }
+    }
+
+    /// Parses the `{kind}` of a `\b{kind}` boundary. A `\b` followed by a count, like in
+    /// `\b{2}`, is a repeated word boundary instead.
+    fn parse_word_boundary_kind(
+        pattern: &mut Peekable<Chars>,
+        flags: Flags,
+    ) -> Result<Pattern, RegexParsingError> {
+        pattern.next();
+        let mut kind = String::new();
+
+        loop {
+            match pattern.next().ok_or(InvalidEscape)? {
+                '}' => break,
+                next_char => kind.push(next_char),
+            }
+        }
+
+        match (kind.as_str(), flags.ascii_only) {
+            ("start-half", false) => Ok(WordStartHalf),
+            ("end-half", false) => Ok(WordEndHalf),
+            ("start-half", true) => Ok(AsciiWordStartHalf),
+            ("end-half", true) => Ok(AsciiWordEndHalf),
+            _ => Err(InvalidEscape),
+        }
+    }
+
+    /// Parses the `{Name}` of `\p{Name}`, or the `L` of `\pL`
+    fn parse_unicode_property(
+        pattern: &mut Peekable<Chars>,
+    ) -> Result<UnicodeProperty, RegexParsingError> {
+        let mut name = String::new();
+
+        if pattern.next_if_eq(&'{').is_some() {
+            loop {
+                match pattern.next().ok_or(InvalidUnicodeProperty)? {
+                    '}' => break,
+                    next_char => name.push(next_char),
+                }
+            }
+        } else {
+            name.push(pattern.next().ok_or(InvalidUnicodeProperty)?);
+        }
+
+        UnicodeProperty::from_name(&name).ok_or(InvalidUnicodeProperty)
+    }
+
+    /// Parses what follows a `[`, up to and including the closing `]`
+    fn parse_char_group(
+        pattern: &mut Peekable<Chars>,
+        flags: Flags,
+    ) -> Result<Pattern, RegexParsingError> {
+        let is_positive_group = pattern.next_if_eq(&'^').is_none();
+        let mut items = Vec::new();
+
+        // A `]` right at the start can't close an empty group, so it's a literal
+        if let Some(closing_bracket) = pattern.next_if_eq(&']') {
+            items.push(CharGroupItem::Char(closing_bracket));
+        }
+
+        loop {
+            let item = match pattern.next().ok_or(UnterminatedCharGroup)? {
+                ']' => break,
+                '\\' => Self::parse_char_group_escape(pattern, flags)?,
+                '[' if pattern.next_if_eq(&':').is_some() => Self::parse_posix_class(pattern)?,
+                next_char => CharGroupItem::Char(next_char),
+            };
+
+            // A `-` is a literal when it can't be a range, i.e. at the start or the end
+            let is_range = pattern.peek() == Some(&'-') && {
+                let mut lookahead = pattern.clone();
+                lookahead.next();
+                lookahead.peek().is_some_and(|&next_char| next_char != ']')
+            };
+
+            if !is_range {
+                items.push(item);
+                continue;
+            }
+
+            pattern.next();
+            let range_end = match pattern.next().ok_or(UnterminatedCharGroup)? {
+                '\\' => Self::parse_char_group_escape(pattern, flags)?,
+                next_char => CharGroupItem::Char(next_char),
+            };
+
+            match (item, range_end) {
+                (CharGroupItem::Char(start), CharGroupItem::Char(end)) if start <= end => {
+                    items.push(CharGroupItem::Range(start, end))
+                }
+                (CharGroupItem::Byte(start), CharGroupItem::Byte(end)) if start <= end => {
+                    items.push(CharGroupItem::ByteRange(start, end))
+                }
+                // Like `[\x00-\xFF]` without Unicode: the ASCII chars, then the bytes
+                (CharGroupItem::Char(start), CharGroupItem::Byte(end)) if start.is_ascii() => {
+                    items.push(CharGroupItem::Range(start, '\x7F'));
+                    items.push(CharGroupItem::ByteRange(0x80, end));
+                }
+                _ => return Err(InvalidCharRange),
+            }
+        }
+
+        if is_positive_group {
+            Ok(PositiveCharGroup(items))
+        } else {
+            Ok(NegativeCharGroup(items))
+        }
+    }
+
+    /// Parses the `name:]` following a `[:` inside brackets
+    fn parse_posix_class(
+        pattern: &mut Peekable<Chars>,
+    ) -> Result<CharGroupItem, RegexParsingError> {
+        let mut name = String::new();
+
+        loop {
+            match pattern.next().ok_or(InvalidPosixClass)? {
+                ':' => break,
+                next_char => name.push(next_char),
+            }
+        }
+
+        if pattern.next() != Some(']') {
+            return Err(InvalidPosixClass);
+        }
+
+        PosixClass::from_name(&name)
+            .map(CharGroupItem::PosixClass)
+            .ok_or(InvalidPosixClass)
+    }
+
+    fn parse_char_group_escape(
+        pattern: &mut Peekable<Chars>,
+        flags: Flags,
+    ) -> Result<CharGroupItem, RegexParsingError> {
+        match pattern.next().ok_or(UnterminatedCharGroup)? {
+            class @ ('w' | 'd' | 's' | 'W' | 'D' | 'S') => {
+                Ok(CharGroupItem::Class(Self::parse_class(class, flags)))
+            }
+            'p' => Ok(CharGroupItem::Class(UnicodePropertyClass(
+                Self::parse_unicode_property(pattern)?,
+            ))),
+            'P' => Ok(CharGroupItem::Class(NotUnicodePropertyClass(
+                Self::parse_unicode_property(pattern)?,
+            ))),
+            escaped_char => {
+                Self::parse_literal_escape(escaped_char, pattern, flags)?.ok_or(InvalidEscape)
+            }
+        }
+    }
+
+    /// Escapes standing for a single char, or a single byte when matching bytes without
+    /// Unicode, both inside and outside brackets. Returns `None` for letters and digits
+    /// that don't form such an escape.
+    fn parse_literal_escape(
+        escaped_char: char,
+        pattern: &mut Peekable<Chars>,
+        flags: Flags,
+    ) -> Result<Option<CharGroupItem>, RegexParsingError> {
+        let escaped_char = match escaped_char {
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            'x' => {
+                let escaped_char = Self::parse_code_point_escape(pattern, true)?;
+
+                match u8::try_from(escaped_char) {
+                    Ok(byte) if byte >= 0x80 && flags.raw_bytes && flags.ascii_only => {
+                        return Ok(Some(CharGroupItem::Byte(byte)));
                     }
+                    _ => escaped_char,
                 }
+            }
+            'u' => Self::parse_code_point_escape(pattern, false)?,
+            // Metacharacters and other punctuation always stand for themselves
+            _ if !escaped_char.is_alphanumeric() => escaped_char,
+            _ => return Ok(None),
+        };
 
-                _ => {
-                    result.push_back(CharLiteral(current_char));
+        Ok(Some(CharGroupItem::Char(escaped_char)))
+    }
+
+    /// Parses the `{H...}` of `\x{H...}` or `\u{H...}`, or the `HH` of `\xHH`
+    fn parse_code_point_escape(
+        pattern: &mut Peekable<Chars>,
+        allow_two_digits: bool,
+    ) -> Result<char, RegexParsingError> {
+        let mut hex_digits = String::new();
+
+        if pattern.next_if_eq(&'{').is_some() {
+            loop {
+                match pattern.next().ok_or(InvalidEscape)? {
+                    '}' => break,
+                    next_char => hex_digits.push(next_char),
                 }
             }
+
+            if hex_digits.is_empty() || hex_digits.len() > MAX_CODE_POINT_DIGITS {
+                return Err(InvalidEscape);
+            }
+        } else if allow_two_digits {
+            for _ in 0..2 {
+                hex_digits.push(pattern.next().ok_or(InvalidEscape)?);
+            }
         }
 
-        if pattern_starts_with {
-            let first_pattern = result.pop_front().unwrap();
-            result.push_front(StartOfString(Box::new(first_pattern)))
+        if hex_digits.is_empty() || !hex_digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+            return Err(InvalidEscape);
         }
 
-        Ok(result)
+        u32::from_str_radix(&hex_digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(InvalidEscape)
     }
 
-    fn find_start_indexes(&self, input: &str) -> Vec<usize> {
-        let mut result = Vec::new();
+    /// Parses what follows a `(`, `(?` starting a special kind of group. Returns `None`
+    /// for `(?flags)`, which only changes `flags` for the rest of the enclosing group.
+    fn parse_group(
+        pattern: &mut Peekable<Chars>,
+        capture_names: &mut Vec<Option<String>>,
+        flags: &mut Flags,
+    ) -> Result<Option<Pattern>, RegexParsingError> {
+        if pattern.next_if_eq(&'?').is_none() {
+            return Self::parse_capture_group(pattern, capture_names, None, *flags).map(Some);
+        }
+
+        let group = match pattern.next().ok_or(InvalidGroup)? {
+            ':' => NonCapturingGroup(Self::parse_pattern(pattern, capture_names, *flags, true)?),
+            '>' => AtomicGroup(Self::parse_pattern(pattern, capture_names, *flags, true)?),
+            '=' => PositiveLookahead(Self::parse_pattern(pattern, capture_names, *flags, true)?),
+            '!' => NegativeLookahead(Self::parse_pattern(pattern, capture_names, *flags, true)?),
+            '<' if pattern.next_if_eq(&'=').is_some() => {
+                PositiveLookbehind(Self::parse_lookbehind(pattern, capture_names, *flags)?)
+            }
+            '<' if pattern.next_if_eq(&'!').is_some() => {
+                NegativeLookbehind(Self::parse_lookbehind(pattern, capture_names, *flags)?)
+            }
+            '<' => {
+                let name = Self::parse_group_name(pattern, '>')?;
+                Self::parse_capture_group(pattern, capture_names, Some(name), *flags)?
+            }
+            'P' if pattern.next_if_eq(&'<').is_some() => {
+                let name = Self::parse_group_name(pattern, '>')?;
+                Self::parse_capture_group(pattern, capture_names, Some(name), *flags)?
+            }
+            flag if "imsxu-".contains(flag) => {
+                let mut group_flags = *flags;
 
-        let first_pattern = self.patterns.front().unwrap();
+                if Self::parse_flags(flag, pattern, &mut group_flags)? == ')' {
+                    *flags = group_flags;
+                    return Ok(None);
+                }
 
-        match first_pattern {
-            StartOfString(_) => {
-                result.push(0);
+                NonCapturingGroup(Self::parse_pattern(
+                    pattern,
+                    capture_names,
+                    group_flags,
+                    true,
+                )?)
             }
-            _ => {
-                // .char_indices() for non-ASCII bytes
-                for (i, _) in input.char_indices() {
-                    let input_sub_range = &mut input[i..].chars().enumerate().peekable();
-                    if first_pattern.matches(input_sub_range, None, &mut vec![]) {
-                        result.push(i);
-                    }
+            _ => return Err(InvalidGroup),
+        };
+
+        Ok(Some(group))
+    }
+
+    /// Lookbehinds are only tried from the starts their maximum length allows, so they
+    /// must have one, like in PCRE
+    fn parse_lookbehind(
+        pattern: &mut Peekable<Chars>,
+        capture_names: &mut Vec<Option<String>>,
+        flags: Flags,
+    ) -> Result<VecDeque<Pattern>, RegexParsingError> {
+        let group = Self::parse_pattern(pattern, capture_names, flags, true)?;
+
+        if max_sequence_width(&group).is_none() {
+            return Err(UnboundedLookbehind);
+        }
+
+        Ok(group)
+    }
+
+    /// Parses flags to enable, optionally followed by `-` and flags to disable, up to and
+    /// including the `)` or `:` ending them, which is returned.
+    fn parse_flags(
+        first_char: char,
+        pattern: &mut Peekable<Chars>,
+        flags: &mut Flags,
+    ) -> Result<char, RegexParsingError> {
+        let mut enable = true;
+        let mut next_char = first_char;
+
+        loop {
+            match next_char {
+                'i' => flags.case_insensitive = enable,
+                'm' => flags.multi_line = enable,
+                's' => flags.dot_matches_new_line = enable,
+                'x' => flags.extended = enable,
+                'u' => flags.ascii_only = !enable,
+                '-' if enable => enable = false,
+                ')' | ':' => return Ok(next_char),
+                _ => return Err(InvalidFlag),
+            }
+
+            next_char = pattern.next().ok_or(InvalidFlag)?;
+        }
+    }
+
+    fn parse_capture_group(
+        pattern: &mut Peekable<Chars>,
+        capture_names: &mut Vec<Option<String>>,
+        name: Option<String>,
+        flags: Flags,
+    ) -> Result<Pattern, RegexParsingError> {
+        if name.is_some() && capture_names.contains(&name) {
+            return Err(DuplicateGroupName);
+        }
+
+        // Groups are numbered by their opening parenthesis, like in backreferences
+        capture_names.push(name);
+        let index = capture_names.len() - 1;
+
+        Ok(Group(
+            index,
+            Self::parse_pattern(pattern, capture_names, flags, true)?,
+        ))
+    }
+
+    /// Parses a group name up to and including `terminator`. Names are made of letters,
+    /// digits and underscores, and can't start with a digit.
+    fn parse_group_name(
+        pattern: &mut Peekable<Chars>,
+        terminator: char,
+    ) -> Result<String, RegexParsingError> {
+        let mut name = String::new();
+
+        loop {
+            match pattern.next().ok_or(InvalidGroupName)? {
+                next_char if next_char == terminator => break,
+                next_char if next_char.is_ascii_alphanumeric() || next_char == '_' => {
+                    name.push(next_char)
                 }
+                _ => return Err(InvalidGroupName),
+            }
+        }
+
+        if name.is_empty() || name.starts_with(|first_char: char| first_char.is_ascii_digit()) {
+            return Err(InvalidGroupName);
+        }
+
+        Ok(name)
+    }
+
+    /// Backreferences take every following digit, `\10` is group 10 rather than group 1
+    /// and a `0`. Use `(?:\1)0` for the latter.
+    fn parse_backreference(
+        first_digit: char,
+        pattern: &mut Peekable<Chars>,
+        flags: Flags,
+    ) -> Result<Pattern, RegexParsingError> {
+        let mut index = first_digit.to_digit(10).ok_or(InvalidBackreference)? as usize;
+
+        while let Some(digit) = pattern.peek().and_then(|next_char| next_char.to_digit(10)) {
+            pattern.next();
+            index = index
+                .checked_mul(10)
+                .and_then(|index| index.checked_add(digit as usize))
+                .ok_or(InvalidBackreference)?;
+        }
+
+        // Group 0 is the whole match, which can't be referenced from inside itself
+        if index == 0 {
+            return Err(InvalidBackreference);
+        }
+
+        Ok(Self::backreference(index, flags))
+    }
+
+    /// Under the `i` flag, backreferences match the captured text in any case
+    fn backreference(index: usize, flags: Flags) -> Pattern {
+        match (flags.case_insensitive, flags.ascii_only) {
+            (false, _) => Backreference(index),
+            (true, false) => CaseInsensitiveBackreference(index),
+            (true, true) => AsciiCaseInsensitiveBackreference(index),
+        }
+    }
+
+    /// Replaces the `\k<name>` backreferences in `pattern` with backreferences to the
+    /// groups they name
+    fn resolve_group_names(
+        pattern: &mut Pattern,
+        capture_names: &[Option<String>],
+    ) -> Result<(), RegexParsingError> {
+        pattern.try_visit_mut(&mut |pattern| {
+            if let NamedBackreference {
+                name,
+                case_insensitive,
+                ascii_only,
+            } = pattern
+            {
+                let index = capture_names
+                    .iter()
+                    .position(|group_name| group_name.as_ref() == Some(name))
+                    .ok_or(UndefinedGroupName)?;
+                let flags = Flags {
+                    case_insensitive: *case_insensitive,
+                    ascii_only: *ascii_only,
+                    ..Flags::default()
+                };
+
+                *pattern = Self::backreference(index, flags);
+            }
+
+            Ok(())
+        })
+    }
+
+    /// A `?` right after a quantifier makes it lazy and a `+` makes it possessive,
+    /// instead of being another quantifier
+    fn push_quantifier(
+        result: &mut VecDeque<Pattern>,
+        pattern: &mut Peekable<Chars>,
+        quantifier: Pattern,
+    ) {
+        if pattern.next_if_eq(&'?').is_some() {
+            result.push_back(LazyQuantifier(Box::new(quantifier)));
+        } else if pattern.next_if_eq(&'+').is_some() {
+            result.push_back(PossessiveQuantifier(Box::new(quantifier)));
+        } else {
+            result.push_back(quantifier);
+        }
+    }
+
+    /// Parses the `n}`, `n,}` or `n,m}` following a `{`
+    fn parse_repetition_bounds(
+        pattern: &mut Peekable<Chars>,
+    ) -> Result<(usize, Option<usize>), RegexParsingError> {
+        let min = Self::parse_repetition_count(pattern)?.ok_or(UnterminatedRepetition)?;
+
+        let max = match pattern.next().ok_or(UnterminatedRepetition)? {
+            '}' => return Ok((min, Some(min))),
+            ',' => Self::parse_repetition_count(pattern)?,
+            _ => return Err(UnterminatedRepetition),
+        };
+
+        if pattern.next() != Some('}') {
+            return Err(UnterminatedRepetition);
+        }
+
+        if max.is_some_and(|max| min > max) {
+            return Err(InvalidRepetitionRange);
+        }
+
+        Ok((min, max))
+    }
+
+    fn parse_repetition_count(
+        pattern: &mut Peekable<Chars>,
+    ) -> Result<Option<usize>, RegexParsingError> {
+        let mut count = None;
+
+        while let Some(digit) = pattern.peek().and_then(|next_char| next_char.to_digit(10)) {
+            pattern.next();
+
+            let new_count = count.unwrap_or(0) * 10 + digit as usize;
+            // Repetitions are unrolled when compiled, so huge counts would blow up the NFA
+            if new_count > MAX_REPETITION_COUNT {
+                return Err(InvalidRepetitionRange);
+            }
+            count = Some(new_count);
+        }
+
+        Ok(count)
+    }
+
+    /// Positions from `from` on where a match could start, skipping those where a first
+    /// pattern consuming a single char can't match
+    fn find_start_indexes<'a>(
+        &'a self,
+        input: Input<'a>,
+        from: usize,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let first_pattern = self.patterns.front();
+        let is_anchored = first_pattern == Some(&StartOfString);
+        let first_char_pattern = first_pattern.filter(|pattern| pattern.is_single_char());
+
+        // Every unit's start, plus the end of the input for patterns that can match the
+        // empty string
+        input
+            .units(from)
+            .map(|(i, _)| i)
+            .chain([input.len()])
+            .take_while(move |&i| !is_anchored || i == 0)
+            .filter(move |&i| {
+                first_char_pattern.map_or(true, |pattern| {
+                    input
+                        .next_unit(i)
+                        .is_some_and(|next_unit| pattern.matches_unit(next_unit))
+                })
+            })
+    }
+
+    /// Leftmost match starting at or after `from`, with the span of every group that
+    /// participated in it. Slot 0 holds the span of the whole match.
+    pub(crate) fn search_at(&self, input: Input, from: usize) -> Option<Captures> {
+        // Backtracking is only needed for what the NFA can't express, like backreferences,
+        // since it's exponential for patterns like `(a|a)*c`
+        if let Some(nfa) = &self.nfa {
+            // The DFA rules out inputs without a match faster than tracking captures
+            if from == 0 && !self.is_match(input) {
+                return None;
+            }
+
+            return pike_vm::search(nfa, input, from);
+        }
+
+        for start in self.find_start_indexes(input, from) {
+            let mut captures: Captures = vec![None; self.capture_names.len()];
+
+            if let Some(end) = backtrack::matches_at(&self.patterns, input, start, &mut captures) {
+                captures[0] = Some((start, end));
+                return Some(captures);
             }
         }
 
-        result
+        None
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::vec_init_then_push)]
 mod parsing_tests {
+    use crate::regex::error::RegexParsingError;
+    use crate::regex::pattern::CharGroupItem::*;
     use crate::regex::pattern::Pattern;
     use crate::regex::pattern::Pattern::*;
+    use crate::regex::pattern::PosixClass::*;
     use crate::regex::regex::Regex;
+    use crate::regex::unicode::GeneralCategory::*;
+    use crate::regex::unicode::{Script, UnicodeProperty};
     use std::collections::VecDeque;
 
     #[test]
@@ -247,7 +1048,7 @@ mod parsing_tests {
 
         assert_eq!(1, regex.patterns.len());
         assert_eq!(
-            PositiveCharGroup(vec!['a']),
+            PositiveCharGroup(vec![Char('a')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -258,7 +1059,7 @@ mod parsing_tests {
 
         assert_eq!(1, regex.patterns.len());
         assert_eq!(
-            PositiveCharGroup(vec!['a', 'b', 'c']),
+            PositiveCharGroup(vec![Char('a'), Char('b'), Char('c')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -269,11 +1070,11 @@ mod parsing_tests {
 
         assert_eq!(2, regex.patterns.len());
         assert_eq!(
-            PositiveCharGroup(vec!['a']),
+            PositiveCharGroup(vec![Char('a')]),
             regex.patterns.pop_front().unwrap()
         );
         assert_eq!(
-            PositiveCharGroup(vec!['b']),
+            PositiveCharGroup(vec![Char('b')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -284,11 +1085,11 @@ mod parsing_tests {
 
         assert_eq!(2, regex.patterns.len());
         assert_eq!(
-            PositiveCharGroup(vec!['a', 'b', 'c']),
+            PositiveCharGroup(vec![Char('a'), Char('b'), Char('c')]),
             regex.patterns.pop_front().unwrap()
         );
         assert_eq!(
-            PositiveCharGroup(vec!['d', 'e', 'f']),
+            PositiveCharGroup(vec![Char('d'), Char('e'), Char('f')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -299,7 +1100,7 @@ mod parsing_tests {
 
         assert_eq!(1, regex.patterns.len());
         assert_eq!(
-            NegativeCharGroup(vec!['a']),
+            NegativeCharGroup(vec![Char('a')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -310,7 +1111,7 @@ mod parsing_tests {
 
         assert_eq!(1, regex.patterns.len());
         assert_eq!(
-            NegativeCharGroup(vec!['a', 'b', 'c']),
+            NegativeCharGroup(vec![Char('a'), Char('b'), Char('c')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -321,11 +1122,11 @@ mod parsing_tests {
 
         assert_eq!(2, regex.patterns.len());
         assert_eq!(
-            NegativeCharGroup(vec!['a']),
+            NegativeCharGroup(vec![Char('a')]),
             regex.patterns.pop_front().unwrap()
         );
         assert_eq!(
-            NegativeCharGroup(vec!['b']),
+            NegativeCharGroup(vec![Char('b')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -336,11 +1137,11 @@ mod parsing_tests {
 
         assert_eq!(2, regex.patterns.len());
         assert_eq!(
-            NegativeCharGroup(vec!['a', 'b', 'c']),
+            NegativeCharGroup(vec![Char('a'), Char('b'), Char('c')]),
             regex.patterns.pop_front().unwrap()
         );
         assert_eq!(
-            NegativeCharGroup(vec!['d', 'e', 'f']),
+            NegativeCharGroup(vec![Char('d'), Char('e'), Char('f')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -351,11 +1152,11 @@ mod parsing_tests {
 
         assert_eq!(2, regex.patterns.len());
         assert_eq!(
-            PositiveCharGroup(vec!['a', 'b', 'c']),
+            PositiveCharGroup(vec![Char('a'), Char('b'), Char('c')]),
             regex.patterns.pop_front().unwrap()
         );
         assert_eq!(
-            NegativeCharGroup(vec!['d', 'e', 'f']),
+            NegativeCharGroup(vec![Char('d'), Char('e'), Char('f')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -366,19 +1167,19 @@ mod parsing_tests {
 
         assert_eq!(4, regex.patterns.len());
         assert_eq!(
-            NegativeCharGroup(vec!['a', 'b', 'c']),
+            NegativeCharGroup(vec![Char('a'), Char('b'), Char('c')]),
             regex.patterns.pop_front().unwrap()
         );
         assert_eq!(
-            PositiveCharGroup(vec!['d', 'e', 'f']),
+            PositiveCharGroup(vec![Char('d'), Char('e'), Char('f')]),
             regex.patterns.pop_front().unwrap()
         );
         assert_eq!(
-            NegativeCharGroup(vec!['g', 'h', 'i']),
+            NegativeCharGroup(vec![Char('g'), Char('h'), Char('i')]),
             regex.patterns.pop_front().unwrap()
         );
         assert_eq!(
-            PositiveCharGroup(vec!['j', 'k', 'l']),
+            PositiveCharGroup(vec![Char('j'), Char('k'), Char('l')]),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -387,22 +1188,18 @@ mod parsing_tests {
     fn start_of_string_with_single_char() {
         let mut regex = Regex::new("^a").unwrap();
 
-        assert_eq!(1, regex.patterns.len());
-        assert_eq!(
-            StartOfString(Box::new(CharLiteral('a'))),
-            regex.patterns.pop_front().unwrap()
-        );
+        assert_eq!(2, regex.patterns.len());
+        assert_eq!(StartOfString, regex.patterns.pop_front().unwrap());
+        assert_eq!(CharLiteral('a'), regex.patterns.pop_front().unwrap());
     }
 
     #[test]
     fn start_of_string_with_multiple_chars() {
         let mut regex = Regex::new("^ab").unwrap();
 
-        assert_eq!(2, regex.patterns.len());
-        assert_eq!(
-            StartOfString(Box::new(CharLiteral('a'))),
-            regex.patterns.pop_front().unwrap()
-        );
+        assert_eq!(3, regex.patterns.len());
+        assert_eq!(StartOfString, regex.patterns.pop_front().unwrap());
+        assert_eq!(CharLiteral('a'), regex.patterns.pop_front().unwrap());
         assert_eq!(CharLiteral('b'), regex.patterns.pop_front().unwrap());
     }
 
@@ -410,23 +1207,19 @@ mod parsing_tests {
     fn end_of_string_with_single_char() {
         let mut regex = Regex::new("a$").unwrap();
 
-        assert_eq!(1, regex.patterns.len());
-        assert_eq!(
-            EndOfString(Box::new(CharLiteral('a'))),
-            regex.patterns.pop_front().unwrap()
-        );
+        assert_eq!(2, regex.patterns.len());
+        assert_eq!(CharLiteral('a'), regex.patterns.pop_front().unwrap());
+        assert_eq!(EndOfString, regex.patterns.pop_front().unwrap());
     }
 
     #[test]
     fn end_of_string_with_multiple_chars() {
         let mut regex = Regex::new("ab$").unwrap();
 
-        assert_eq!(2, regex.patterns.len());
+        assert_eq!(3, regex.patterns.len());
         assert_eq!(CharLiteral('a'), regex.patterns.pop_front().unwrap());
-        assert_eq!(
-            EndOfString(Box::new(CharLiteral('b'))),
-            regex.patterns.pop_front().unwrap()
-        );
+        assert_eq!(CharLiteral('b'), regex.patterns.pop_front().unwrap());
+        assert_eq!(EndOfString, regex.patterns.pop_front().unwrap());
     }
 
     #[test]
@@ -461,7 +1254,11 @@ mod parsing_tests {
 
         assert_eq!(1, regex.patterns.len());
         assert_eq!(
-            OneOrMoreQuantifier(Box::new(PositiveCharGroup(vec!['a', 'b', 'c']))),
+            OneOrMoreQuantifier(Box::new(PositiveCharGroup(vec![
+                Char('a'),
+                Char('b'),
+                Char('c')
+            ]))),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -498,7 +1295,11 @@ mod parsing_tests {
 
         assert_eq!(1, regex.patterns.len());
         assert_eq!(
-            OptionalQuantifier(Box::new(PositiveCharGroup(vec!['a', 'b', 'c']))),
+            OptionalQuantifier(Box::new(PositiveCharGroup(vec![
+                Char('a'),
+                Char('b'),
+                Char('c')
+            ]))),
             regex.patterns.pop_front().unwrap()
         );
     }
@@ -529,7 +1330,7 @@ mod parsing_tests {
         expected.push_back(CharLiteral('a'));
 
         assert_eq!(1, regex.patterns.len());
-        assert_eq!(Group(expected), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(1, expected), regex.patterns.pop_front().unwrap());
     }
 
     #[test]
@@ -542,7 +1343,7 @@ mod parsing_tests {
         expected.push_back(CharLiteral('c'));
 
         assert_eq!(1, regex.patterns.len());
-        assert_eq!(Group(expected), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(1, expected), regex.patterns.pop_front().unwrap());
     }
 
     #[test]
@@ -556,8 +1357,8 @@ mod parsing_tests {
         expected_2.push_back(CharLiteral('b'));
 
         assert_eq!(2, regex.patterns.len());
-        assert_eq!(Group(expected_1), regex.patterns.pop_front().unwrap());
-        assert_eq!(Group(expected_2), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(1, expected_1), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(2, expected_2), regex.patterns.pop_front().unwrap());
     }
 
     #[test]
@@ -574,7 +1375,7 @@ mod parsing_tests {
         });
 
         assert_eq!(1, regex.patterns.len());
-        assert_eq!(Group(expected), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(1, expected), regex.patterns.pop_front().unwrap());
     }
 
     #[test]
@@ -603,7 +1404,7 @@ mod parsing_tests {
         });
 
         assert_eq!(1, regex.patterns.len());
-        assert_eq!(Group(expected), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(1, expected), regex.patterns.pop_front().unwrap());
     }
 
     #[test]
@@ -621,8 +1422,8 @@ mod parsing_tests {
         expected_2.push_back(CharLiteral('f'));
 
         assert_eq!(2, regex.patterns.len());
-        assert_eq!(Group(expected_1), regex.patterns.pop_front().unwrap());
-        assert_eq!(Group(expected_2), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(1, expected_1), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(2, expected_2), regex.patterns.pop_front().unwrap());
     }
 
     #[test]
@@ -634,10 +1435,10 @@ mod parsing_tests {
 
         let mut inner_expected = VecDeque::new();
         inner_expected.push_back(CharLiteral('b'));
-        expected.push_back(Group(inner_expected));
+        expected.push_back(Group(2, inner_expected));
 
         assert_eq!(1, regex.patterns.len());
-        assert_eq!(Group(expected), regex.patterns.pop_front().unwrap());
+        assert_eq!(Group(1, expected), regex.patterns.pop_front().unwrap());
     }
 
     #[test]
@@ -663,17 +1464,17 @@ mod parsing_tests {
                     vec_e_to_f.push_back(CharLiteral('e'));
                     vec_e_to_f.push_back(CharLiteral('f'));
 
-                    Group(vec_e_to_f)
+                    Group(3, vec_e_to_f)
                 });
 
-                Group(vec_c_to_f)
+                Group(2, vec_c_to_f)
             });
 
             vec_a_to_h.push_back(CharLiteral(' '));
             vec_a_to_h.push_back(CharLiteral('g'));
             vec_a_to_h.push_back(CharLiteral('h'));
 
-            Group(vec_a_to_h)
+            Group(1, vec_a_to_h)
         });
 
         expected.push_back(CharLiteral(' '));
@@ -682,10 +1483,906 @@ mod parsing_tests {
             let mut vec_i_to_j = VecDeque::new();
             vec_i_to_j.push_back(CharLiteral('i'));
             vec_i_to_j.push_back(CharLiteral('j'));
-            Group(vec_i_to_j)
+            Group(4, vec_i_to_j)
         });
 
         assert_eq!(3, regex.patterns.len());
         assert_eq!(expected, regex.patterns);
     }
+
+    #[test]
+    fn exact_repetition_with_single_char() {
+        let mut regex = Regex::new("a{3}").unwrap();
+
+        assert_eq!(1, regex.patterns.len());
+        assert_eq!(
+            Repetition {
+                pattern: Box::new(CharLiteral('a')),
+                min: 3,
+                max: Some(3)
+            },
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn at_least_repetition_with_digit_class() {
+        let mut regex = Regex::new("\\d{2,}").unwrap();
+
+        assert_eq!(1, regex.patterns.len());
+        assert_eq!(
+            Repetition {
+                pattern: Box::new(DigitClass),
+                min: 2,
+                max: None
+            },
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn bounded_repetition_with_multi_digit_counts() {
+        let mut regex = Regex::new("a{10,12}b").unwrap();
+
+        assert_eq!(2, regex.patterns.len());
+        assert_eq!(
+            Repetition {
+                pattern: Box::new(CharLiteral('a')),
+                min: 10,
+                max: Some(12)
+            },
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(CharLiteral('b'), regex.patterns.pop_front().unwrap());
+    }
+
+    #[test]
+    fn brace_without_count_is_literal() {
+        let mut regex = Regex::new("{a}").unwrap();
+
+        assert_eq!(3, regex.patterns.len());
+        assert_eq!(CharLiteral('{'), regex.patterns.pop_front().unwrap());
+        assert_eq!(CharLiteral('a'), regex.patterns.pop_front().unwrap());
+        assert_eq!(CharLiteral('}'), regex.patterns.pop_front().unwrap());
+    }
+
+    #[test]
+    fn repetition_without_pattern_is_invalid() {
+        assert!(matches!(
+            Regex::new("{2}"),
+            Err(RegexParsingError::InvalidRepetitionQuantifier)
+        ));
+    }
+
+    #[test]
+    fn unterminated_repetition_is_invalid() {
+        assert!(matches!(
+            Regex::new("a{2"),
+            Err(RegexParsingError::UnterminatedRepetition)
+        ));
+        assert!(matches!(
+            Regex::new("a{2,5"),
+            Err(RegexParsingError::UnterminatedRepetition)
+        ));
+        assert!(matches!(
+            Regex::new("a{2x}"),
+            Err(RegexParsingError::UnterminatedRepetition)
+        ));
+    }
+
+    #[test]
+    fn repetition_with_min_greater_than_max_is_invalid() {
+        assert!(matches!(
+            Regex::new("a{3,2}"),
+            Err(RegexParsingError::InvalidRepetitionRange)
+        ));
+    }
+
+    #[test]
+    fn repetition_over_limit_is_invalid() {
+        assert!(matches!(
+            Regex::new("a{100000}"),
+            Err(RegexParsingError::InvalidRepetitionRange)
+        ));
+    }
+
+    #[test]
+    fn nested_repetitions_unrolled_over_size_limit_are_too_big() {
+        assert!(matches!(
+            Regex::new("x{1000}{100}"),
+            Err(RegexParsingError::CompiledTooBig)
+        ));
+        assert!(matches!(
+            Regex::new("(?:a|a){1,1000}{1,200}b"),
+            Err(RegexParsingError::CompiledTooBig)
+        ));
+    }
+
+    #[test]
+    fn single_zero_or_more_quantifier_with_single_char() {
+        let mut regex = Regex::new("ab*").unwrap();
+
+        assert_eq!(2, regex.patterns.len());
+        assert_eq!(CharLiteral('a'), regex.patterns.pop_front().unwrap());
+        assert_eq!(
+            ZeroOrMoreQuantifier(Box::new(CharLiteral('b'))),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn lazy_quantifiers() {
+        let mut regex = Regex::new("a*?b+?c??d{1,2}?").unwrap();
+
+        assert_eq!(4, regex.patterns.len());
+        assert_eq!(
+            LazyQuantifier(Box::new(ZeroOrMoreQuantifier(Box::new(CharLiteral('a'))))),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            LazyQuantifier(Box::new(OneOrMoreQuantifier(Box::new(CharLiteral('b'))))),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            LazyQuantifier(Box::new(OptionalQuantifier(Box::new(CharLiteral('c'))))),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            LazyQuantifier(Box::new(Repetition {
+                pattern: Box::new(CharLiteral('d')),
+                min: 1,
+                max: Some(2)
+            })),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn zero_or_more_without_pattern_is_invalid() {
+        assert!(matches!(
+            Regex::new("*a"),
+            Err(RegexParsingError::InvalidZeroOrMoreQuantifier)
+        ));
+    }
+
+    #[test]
+    fn possessive_quantifiers() {
+        let mut regex = Regex::new("a*+b++c?+d{2}+").unwrap();
+
+        assert_eq!(4, regex.patterns.len());
+        assert_eq!(
+            PossessiveQuantifier(Box::new(ZeroOrMoreQuantifier(Box::new(CharLiteral('a'))))),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            PossessiveQuantifier(Box::new(OneOrMoreQuantifier(Box::new(CharLiteral('b'))))),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            PossessiveQuantifier(Box::new(OptionalQuantifier(Box::new(CharLiteral('c'))))),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            PossessiveQuantifier(Box::new(Repetition {
+                pattern: Box::new(CharLiteral('d')),
+                min: 2,
+                max: Some(2)
+            })),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn atomic_group_doesnt_capture() {
+        let mut regex = Regex::new("(?>ab)(c)").unwrap();
+
+        assert_eq!(2, regex.patterns.len());
+        assert_eq!(
+            AtomicGroup(VecDeque::from([CharLiteral('a'), CharLiteral('b')])),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            Group(1, VecDeque::from([CharLiteral('c')])),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_group_kind_is_invalid() {
+        assert!(matches!(
+            Regex::new("(?%ab)"),
+            Err(RegexParsingError::InvalidGroup)
+        ));
+    }
+
+    #[test]
+    fn unterminated_group_is_invalid() {
+        assert!(matches!(
+            Regex::new("(a"),
+            Err(RegexParsingError::UnterminatedGroup)
+        ));
+        assert!(matches!(
+            Regex::new("(?:a(b)"),
+            Err(RegexParsingError::UnterminatedGroup)
+        ));
+    }
+
+    #[test]
+    fn unmatched_closing_parenthesis_is_invalid() {
+        assert!(matches!(
+            Regex::new("a)b"),
+            Err(RegexParsingError::UnmatchedClosingParenthesis)
+        ));
+        assert!(matches!(
+            Regex::new("(a))"),
+            Err(RegexParsingError::UnmatchedClosingParenthesis)
+        ));
+    }
+
+    #[test]
+    fn char_group_with_ranges() {
+        let mut regex = Regex::new("[a-z0-9_]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![Range('a', 'z'), Range('0', '9'), Char('_')]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn char_group_with_literal_dashes() {
+        let mut regex = Regex::new("[-a-][a\\-z]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![Char('-'), Char('a'), Char('-')]),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            PositiveCharGroup(vec![Char('a'), Char('-'), Char('z')]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn char_group_with_escapes() {
+        let mut regex = Regex::new("[\\]\\\\\\n\\t\\[]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![
+                Char(']'),
+                Char('\\'),
+                Char('\n'),
+                Char('\t'),
+                Char('[')
+            ]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn char_group_with_embedded_classes() {
+        let mut regex = Regex::new("[\\d\\w_]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![Class(DigitClass), Class(AlphanumericClass), Char('_')]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn char_group_with_leading_closing_bracket() {
+        let mut regex = Regex::new("[]a][^]a]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![Char(']'), Char('a')]),
+            regex.patterns.pop_front().unwrap()
+        );
+        assert_eq!(
+            NegativeCharGroup(vec![Char(']'), Char('a')]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn caret_after_start_of_char_group_is_literal() {
+        let mut regex = Regex::new("[a^b]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![Char('a'), Char('^'), Char('b')]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn unterminated_char_group_is_invalid() {
+        assert!(matches!(
+            Regex::new("[abc"),
+            Err(RegexParsingError::UnterminatedCharGroup)
+        ));
+        assert!(matches!(
+            Regex::new("[a-"),
+            Err(RegexParsingError::UnterminatedCharGroup)
+        ));
+    }
+
+    #[test]
+    fn reversed_char_range_is_invalid() {
+        assert!(matches!(
+            Regex::new("[z-a]"),
+            Err(RegexParsingError::InvalidCharRange)
+        ));
+    }
+
+    #[test]
+    fn char_range_with_class_is_invalid() {
+        assert!(matches!(
+            Regex::new("[\\d-z]"),
+            Err(RegexParsingError::InvalidCharRange)
+        ));
+    }
+
+    #[test]
+    fn char_group_with_posix_classes() {
+        let mut regex = Regex::new("[[:alpha:][:digit:]_]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![PosixClass(Alpha), PosixClass(Digit), Char('_')]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn negative_char_group_with_posix_class() {
+        let mut regex = Regex::new("[^[:space:]]").unwrap();
+
+        assert_eq!(
+            NegativeCharGroup(vec![PosixClass(Space)]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn bracket_without_colon_in_char_group_is_literal() {
+        let mut regex = Regex::new("[[a]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![Char('['), Char('a')]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_posix_class_is_invalid() {
+        assert!(matches!(
+            Regex::new("[[:letter:]]"),
+            Err(RegexParsingError::InvalidPosixClass)
+        ));
+    }
+
+    #[test]
+    fn unterminated_posix_class_is_invalid() {
+        assert!(matches!(
+            Regex::new("[[:alpha]"),
+            Err(RegexParsingError::InvalidPosixClass)
+        ));
+    }
+
+    #[test]
+    fn perl_shorthand_classes() {
+        let regex = Regex::new("\\s\\S\\D\\W").unwrap();
+
+        assert_eq!(
+            VecDeque::from([
+                WhitespaceClass,
+                NotWhitespaceClass,
+                NotDigitClass,
+                NotAlphanumericClass
+            ]),
+            regex.patterns
+        );
+    }
+
+    #[test]
+    fn word_boundaries() {
+        let regex = Regex::new("\\bfoo\\B").unwrap();
+
+        assert_eq!(
+            VecDeque::from([
+                WordBoundary,
+                CharLiteral('f'),
+                CharLiteral('o'),
+                CharLiteral('o'),
+                NotWordBoundary
+            ]),
+            regex.patterns
+        );
+    }
+
+    #[test]
+    fn ascii_only_word_boundaries() {
+        let regex = Regex::new("(?-u)\\b\\B\\b{start-half}\\b{end-half}").unwrap();
+
+        assert_eq!(
+            VecDeque::from([
+                AsciiWordBoundary,
+                AsciiNotWordBoundary,
+                AsciiWordStartHalf,
+                AsciiWordEndHalf
+            ]),
+            regex.patterns
+        );
+    }
+
+    #[test]
+    fn half_word_boundaries() {
+        let regex = Regex::new("\\b{start-half}a\\b{end-half}").unwrap();
+
+        assert_eq!(
+            VecDeque::from([WordStartHalf, CharLiteral('a'), WordEndHalf]),
+            regex.patterns
+        );
+        assert!(matches!(
+            Regex::new("\\b{middle}"),
+            Err(RegexParsingError::InvalidEscape)
+        ));
+    }
+
+    #[test]
+    fn char_group_with_negated_shorthand_classes() {
+        let mut regex = Regex::new("[\\s\\S]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![Class(WhitespaceClass), Class(NotWhitespaceClass)]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn escaped_metacharacters() {
+        let regex = Regex::new("\\.\\+\\?\\*\\(\\)\\[\\]\\{\\}\\|\\^\\$\\\\\\/").unwrap();
+
+        assert_eq!(
+            VecDeque::from_iter(".+?*()[]{}|^$\\/".chars().map(CharLiteral)),
+            regex.patterns
+        );
+    }
+
+    #[test]
+    fn control_char_escapes() {
+        let regex = Regex::new("\\t\\n\\r").unwrap();
+
+        assert_eq!(
+            VecDeque::from([CharLiteral('\t'), CharLiteral('\n'), CharLiteral('\r')]),
+            regex.patterns
+        );
+    }
+
+    #[test]
+    fn code_point_escapes() {
+        let regex = Regex::new("\\x41\\x{e9}\\u{1F600}").unwrap();
+
+        assert_eq!(
+            VecDeque::from([CharLiteral('A'), CharLiteral('é'), CharLiteral('😀')]),
+            regex.patterns
+        );
+    }
+
+    #[test]
+    fn code_point_escape_in_char_group() {
+        let mut regex = Regex::new("[\\x41-\\x{5A}]").unwrap();
+
+        assert_eq!(
+            PositiveCharGroup(vec![Range('A', 'Z')]),
+            regex.patterns.pop_front().unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_escape_is_invalid() {
+        assert!(matches!(
+            Regex::new("\\q"),
+            Err(RegexParsingError::InvalidEscape)
+        ));
+        assert!(matches!(
+            Regex::new("[\\q]"),
+            Err(RegexParsingError::InvalidEscape)
+        ));
+    }
+
+    #[test]
+    fn malformed_code_point_escapes_are_invalid() {
+        for pattern in [
+            "\\x4",
+            "\\xZZ",
+            "\\x{}",
+            "\\x{110000}",
+            "\\x{D800}",
+            "\\x{1234567}",
+            "\\u{41",
+            "\\u0041",
+        ] {
+            assert!(
+                matches!(Regex::new(pattern), Err(RegexParsingError::InvalidEscape)),
+                "{pattern} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn alternation_with_quantified_classes() {
+        let mut regex = Regex::new("(\\d+|[a-z]+)").unwrap();
+
+        let expected = VecDeque::from([Alternation(vec![
+            vec![OneOrMoreQuantifier(Box::new(DigitClass))],
+            vec![OneOrMoreQuantifier(Box::new(PositiveCharGroup(vec![
+                Range('a', 'z'),
+            ])))],
+        ])]);
+
+        assert_eq!(1, regex.patterns.len());
+        assert_eq!(Group(1, expected), regex.patterns.pop_front().unwrap());
+    }
+
+    #[test]
+    fn alternation_without_group() {
+        let regex = Regex::new("foo|ba.").unwrap();
+
+        let expected = VecDeque::from([Alternation(vec![
+            vec![CharLiteral('f'), CharLiteral('o'), CharLiteral('o')],
+            vec![CharLiteral('b'), CharLiteral('a'), Wildcard],
+        ])]);
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn nested_alternations() {
+        let mut regex = Regex::new("(a|(b|c))d").unwrap();
+
+        let inner = Group(
+            2,
+            VecDeque::from([Alternation(vec![
+                vec![CharLiteral('b')],
+                vec![CharLiteral('c')],
+            ])]),
+        );
+        let expected = VecDeque::from([Alternation(vec![vec![CharLiteral('a')], vec![inner]])]);
+
+        assert_eq!(2, regex.patterns.len());
+        assert_eq!(Group(1, expected), regex.patterns.pop_front().unwrap());
+        assert_eq!(CharLiteral('d'), regex.patterns.pop_front().unwrap());
+    }
+
+    #[test]
+    fn alternation_with_anchored_branches() {
+        let regex = Regex::new("^a|b$").unwrap();
+
+        let expected = VecDeque::from([Alternation(vec![
+            vec![StartOfString, CharLiteral('a')],
+            vec![CharLiteral('b'), EndOfString],
+        ])]);
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn alternation_with_empty_branch() {
+        let mut regex = Regex::new("(a|)").unwrap();
+
+        let expected = VecDeque::from([Alternation(vec![vec![CharLiteral('a')], vec![]])]);
+
+        assert_eq!(Group(1, expected), regex.patterns.pop_front().unwrap());
+    }
+
+    #[test]
+    fn non_capturing_group_doesnt_take_group_number() {
+        let regex = Regex::new("(?:ab)(c)").unwrap();
+
+        assert_eq!(
+            VecDeque::from([
+                NonCapturingGroup(VecDeque::from([CharLiteral('a'), CharLiteral('b')])),
+                Group(1, VecDeque::from([CharLiteral('c')]))
+            ]),
+            regex.patterns
+        );
+        assert_eq!(vec![None, None], *regex.capture_names);
+    }
+
+    #[test]
+    fn named_groups() {
+        let regex = Regex::new("(?<year>\\d)(x)(?P<day>\\d)").unwrap();
+
+        assert_eq!(
+            VecDeque::from([
+                Group(1, VecDeque::from([DigitClass])),
+                Group(2, VecDeque::from([CharLiteral('x')])),
+                Group(3, VecDeque::from([DigitClass]))
+            ]),
+            regex.patterns
+        );
+        assert_eq!(
+            vec![
+                None,
+                Some("year".to_string()),
+                None,
+                Some("day".to_string())
+            ],
+            *regex.capture_names
+        );
+    }
+
+    #[test]
+    fn named_backreference() {
+        let mut regex = Regex::new("(a)(?<b_2>b)\\k<b_2>").unwrap();
+
+        assert_eq!(3, regex.patterns.len());
+        assert_eq!(Backreference(2), regex.patterns.pop_back().unwrap());
+    }
+
+    #[test]
+    fn named_backreference_before_its_group() {
+        let regex = Regex::new("\\k<n>(?i:(?:b|\\k<n>)+)(?<n>a)").unwrap();
+
+        assert_eq!(Backreference(1), regex.patterns[0]);
+        assert!(regex.patterns[1].contains(&|pattern| *pattern == CaseInsensitiveBackreference(1)));
+    }
+
+    #[test]
+    fn invalid_group_names() {
+        for pattern in ["(?<>a)", "(?<1st>a)", "(?<a-b>a)", "(?<abc", "(?P<a b>c)"] {
+            assert!(
+                matches!(
+                    Regex::new(pattern),
+                    Err(RegexParsingError::InvalidGroupName)
+                ),
+                "{pattern} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn duplicate_group_name_is_invalid() {
+        assert!(matches!(
+            Regex::new("(?<a>x)(?P<a>y)"),
+            Err(RegexParsingError::DuplicateGroupName)
+        ));
+    }
+
+    #[test]
+    fn undefined_group_name_is_invalid() {
+        assert!(matches!(
+            Regex::new("(?<a>x)\\k<b>"),
+            Err(RegexParsingError::UndefinedGroupName)
+        ));
+    }
+
+    #[test]
+    fn multi_digit_backreference() {
+        let regex = Regex::new("(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\\10").unwrap();
+
+        assert!(matches!(regex.patterns.back(), Some(Backreference(10))));
+    }
+
+    #[test]
+    fn backreference_to_undefined_group_is_invalid() {
+        assert!(matches!(
+            Regex::new("(a)(b)\\3"),
+            Err(RegexParsingError::UndefinedGroupNumber)
+        ));
+    }
+
+    #[test]
+    fn backreference_to_group_zero_is_invalid() {
+        assert!(matches!(
+            Regex::new("(a)\\0"),
+            Err(RegexParsingError::InvalidBackreference)
+        ));
+    }
+
+    #[test]
+    fn forward_backreference_is_valid() {
+        assert!(Regex::new("(\\2a|(b))+").is_ok());
+    }
+
+    #[test]
+    fn lookarounds() {
+        let mut regex = Regex::new("(?=a)(?!b)(?<=c)(?<!d)").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(PositiveLookahead(VecDeque::from([CharLiteral('a')])));
+        expected.push_back(NegativeLookahead(VecDeque::from([CharLiteral('b')])));
+        expected.push_back(PositiveLookbehind(VecDeque::from([CharLiteral('c')])));
+        expected.push_back(NegativeLookbehind(VecDeque::from([CharLiteral('d')])));
+
+        assert_eq!(expected, regex.patterns);
+        assert!(regex.nfa.take().is_none());
+    }
+
+    #[test]
+    fn invalid_lookbehind_is_invalid_group_name() {
+        assert!(matches!(
+            Regex::new("(?<-a)"),
+            Err(RegexParsingError::InvalidGroupName)
+        ));
+    }
+
+    #[test]
+    fn unbounded_lookbehind_is_invalid() {
+        assert!(matches!(
+            Regex::new("(?<=ab+)c"),
+            Err(RegexParsingError::UnboundedLookbehind)
+        ));
+        assert!(matches!(
+            Regex::new("(a)(?<!x|\\1)"),
+            Err(RegexParsingError::UnboundedLookbehind)
+        ));
+        assert!(Regex::new("(?<=a{2,5}|(?:bc)?)d").is_ok());
+    }
+
+    #[test]
+    fn case_insensitive_flag() {
+        let regex = Regex::new("(?i)a1[b]").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(CaseInsensitive(Box::new(CharLiteral('a'))));
+        expected.push_back(CharLiteral('1'));
+        expected.push_back(CaseInsensitive(Box::new(PositiveCharGroup(vec![Char(
+            'b',
+        )]))));
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn scoped_flag_group() {
+        let regex = Regex::new("(?i:a)a").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(NonCapturingGroup(VecDeque::from([CaseInsensitive(
+            Box::new(CharLiteral('a')),
+        )])));
+        expected.push_back(CharLiteral('a'));
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn flag_ends_with_enclosing_group() {
+        let regex = Regex::new("((?i)a)a").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(Group(
+            1,
+            VecDeque::from([CaseInsensitive(Box::new(CharLiteral('a')))]),
+        ));
+        expected.push_back(CharLiteral('a'));
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn disabled_flag() {
+        let regex = Regex::new("(?i)a(?-i)a").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(CaseInsensitive(Box::new(CharLiteral('a'))));
+        expected.push_back(CharLiteral('a'));
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn multi_line_and_dot_all_flags() {
+        let regex = Regex::new("(?ms)^.$").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(StartOfLine);
+        expected.push_back(AnyChar);
+        expected.push_back(EndOfLine);
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn extended_flag() {
+        let regex = Regex::new("(?x) a \\  b+ # comment\n c").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(CharLiteral('a'));
+        expected.push_back(CharLiteral(' '));
+        expected.push_back(OneOrMoreQuantifier(Box::new(CharLiteral('b'))));
+        expected.push_back(CharLiteral('c'));
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn invalid_flags() {
+        for pattern in ["(?iq)a", "(?i-m-s)a", "(?i"] {
+            assert!(
+                matches!(Regex::new(pattern), Err(RegexParsingError::InvalidFlag)),
+                "{pattern} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn anchors_alone() {
+        let regex = Regex::new("^$").unwrap();
+
+        assert_eq!(VecDeque::from([StartOfString, EndOfString]), regex.patterns);
+    }
+
+    #[test]
+    fn start_anchor_alone() {
+        let regex = Regex::new("^").unwrap();
+
+        assert_eq!(VecDeque::from([StartOfString]), regex.patterns);
+    }
+
+    #[test]
+    fn escaped_anchors() {
+        let regex = Regex::new("\\Aa\\z|b\\Z").unwrap();
+
+        let expected = VecDeque::from([Alternation(vec![
+            vec![StartOfString, CharLiteral('a'), EndOfString],
+            vec![CharLiteral('b'), EndOfStringBeforeNewline],
+        ])]);
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn unicode_property_classes() {
+        let regex = Regex::new("\\p{Greek}\\PL[\\pN]").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(UnicodePropertyClass(UnicodeProperty::Script(Script::Greek)));
+        expected.push_back(NotUnicodePropertyClass(
+            UnicodeProperty::general_categories(&[Lu, Ll, Lt, Lm, Lo]),
+        ));
+        expected.push_back(PositiveCharGroup(vec![Class(UnicodePropertyClass(
+            UnicodeProperty::general_categories(&[Nd, Nl, No]),
+        ))]));
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn invalid_unicode_properties() {
+        for pattern in ["\\p{Klingon}", "\\p{L", "\\p"] {
+            assert!(
+                matches!(
+                    Regex::new(pattern),
+                    Err(RegexParsingError::InvalidUnicodeProperty)
+                ),
+                "{pattern} should be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn ascii_only_classes() {
+        let regex = Regex::new("(?-u)\\d\\W[\\s]").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(PositiveCharGroup(vec![PosixClass(Digit)]));
+        expected.push_back(NegativeCharGroup(vec![PosixClass(Alnum), Char('_')]));
+        expected.push_back(PositiveCharGroup(vec![Class(PositiveCharGroup(vec![
+            PosixClass(Space),
+        ]))]));
+
+        assert_eq!(expected, regex.patterns);
+    }
+
+    #[test]
+    fn ascii_only_case_insensitive() {
+        let regex = Regex::new("(?i-u)aé").unwrap();
+
+        let mut expected = VecDeque::new();
+        expected.push_back(AsciiCaseInsensitive(Box::new(CharLiteral('a'))));
+        expected.push_back(CharLiteral('é'));
+
+        assert_eq!(expected, regex.patterns);
+    }
 }
@@ -0,0 +1,79 @@
+use crate::regex::search::Captures;
+
+/// Produces the replacement of each match for `Regex::replace` and its variants
+pub trait Replacer {
+    /// Appends the replacement of the match described by `captures` to `destination`
+    fn replace_append(&mut self, captures: &Captures<'_>, destination: &mut String);
+}
+
+/// A template where `$1`, `${1}`, `$name` and `${name}` stand for what a group matched,
+/// empty if it didn't participate, and `$$` for a `$`. References without braces take
+/// every following letter, digit and underscore, so `$1a` refers to a group named `1a`,
+/// use `${1}a` instead.
+impl Replacer for &str {
+    fn replace_append(&mut self, captures: &Captures<'_>, destination: &mut String) {
+        expand(self, captures, destination);
+    }
+}
+
+impl Replacer for String {
+    fn replace_append(&mut self, captures: &Captures<'_>, destination: &mut String) {
+        expand(self, captures, destination);
+    }
+}
+
+/// The closure is called with the groups of each match, and returns its replacement as is
+impl<F, T> Replacer for F
+where
+    F: FnMut(&Captures<'_>) -> T,
+    T: AsRef<str>,
+{
+    fn replace_append(&mut self, captures: &Captures<'_>, destination: &mut String) {
+        destination.push_str(self(captures).as_ref());
+    }
+}
+
+/// A `$` not followed by a group reference or another `$` is kept as a literal, like
+/// the one in `${unterminated`
+fn expand(template: &str, captures: &Captures<'_>, destination: &mut String) {
+    let mut rest = template;
+
+    while let Some(dollar) = rest.find('$') {
+        destination.push_str(&rest[..dollar]);
+        rest = &rest[dollar + 1..];
+
+        if let Some(after_dollar) = rest.strip_prefix('$') {
+            destination.push('$');
+            rest = after_dollar;
+            continue;
+        }
+
+        let (reference, after_reference) = match rest.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(closing_brace) => (&braced[..closing_brace], &braced[closing_brace + 1..]),
+                None => ("", rest),
+            },
+            None => {
+                let reference_len = rest
+                    .find(|next_char: char| !next_char.is_ascii_alphanumeric() && next_char != '_')
+                    .unwrap_or(rest.len());
+                (&rest[..reference_len], &rest[reference_len..])
+            }
+        };
+
+        if reference.is_empty() {
+            destination.push('$');
+            continue;
+        }
+
+        let group = match reference.parse::<usize>() {
+            Ok(index) => captures.get(index),
+            Err(_) => captures.name(reference),
+        };
+
+        destination.push_str(group.map_or("", |group| group.as_str()));
+        rest = after_reference;
+    }
+
+    destination.push_str(rest);
+}
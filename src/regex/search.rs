@@ -0,0 +1,278 @@
+use crate::regex::pattern::Captures as Spans;
+use crate::regex::regex::Regex;
+use std::ops::{Index, Range};
+use std::sync::Arc;
+
+/// Span of `input` matched by a regex or one of its groups, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match<'h> {
+    input: &'h str,
+    start: usize,
+    end: usize,
+}
+
+impl<'h> Match<'h> {
+    pub(crate) fn new(input: &'h str, start: usize, end: usize) -> Self {
+        Self { input, start, end }
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.end
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    pub fn as_str(&self) -> &'h str {
+        &self.input[self.range()]
+    }
+}
+
+/// Successive non-overlapping searches through an input, yielding the spans of the whole
+/// match and of every group for each match.
+pub(crate) struct Searches<'r, 'h> {
+    regex: &'r Regex,
+    input: &'h str,
+    position: usize,
+    last_match_end: Option<usize>,
+}
+
+impl<'r, 'h> Searches<'r, 'h> {
+    pub(crate) fn new(regex: &'r Regex, input: &'h str) -> Self {
+        Self {
+            regex,
+            input,
+            position: 0,
+            last_match_end: None,
+        }
+    }
+}
+
+impl Iterator for Searches<'_, '_> {
+    type Item = Spans;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.position > self.input.len() {
+                return None;
+            }
+
+            let captures = self.regex.search_at(self.input.into(), self.position)?;
+            let (start, end) = captures[0]?;
+
+            self.position = if start == end {
+                // An empty match doesn't move the search forward, skip the next char so
+                // the same match isn't found again
+                end + self.input[end..].chars().next().map_or(1, char::len_utf8)
+            } else {
+                end
+            };
+
+            if start == end && self.last_match_end == Some(end) {
+                continue;
+            }
+
+            self.last_match_end = Some(end);
+            return Some(captures);
+        }
+    }
+}
+
+/// Iterator over the matches of `Regex::find_iter`
+pub struct Matches<'r, 'h> {
+    searches: Searches<'r, 'h>,
+}
+
+impl<'r, 'h> Matches<'r, 'h> {
+    pub(crate) fn new(regex: &'r Regex, input: &'h str) -> Self {
+        Self {
+            searches: Searches::new(regex, input),
+        }
+    }
+}
+
+impl<'h> Iterator for Matches<'_, 'h> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let captures = self.searches.next()?;
+        let (start, end) = captures[0]?;
+
+        Some(Match::new(self.searches.input, start, end))
+    }
+}
+
+/// What every group of a regex matched in one match. Groups are numbered by their
+/// opening parenthesis starting at 1, group 0 being the whole match.
+#[derive(Debug, Clone)]
+pub struct Captures<'h> {
+    input: &'h str,
+    spans: Spans,
+    names: Arc<[Option<String>]>,
+}
+
+impl<'h> Captures<'h> {
+    pub(crate) fn new(input: &'h str, spans: Spans, names: Arc<[Option<String>]>) -> Self {
+        Self {
+            input,
+            spans,
+            names,
+        }
+    }
+
+    /// Match of the group with number `index`, `None` if there's no such group or if it
+    /// didn't participate in the match, e.g. `(a)?` against `b`
+    pub fn get(&self, index: usize) -> Option<Match<'h>> {
+        let (start, end) = (*self.spans.get(index)?)?;
+
+        Some(Match::new(self.input, start, end))
+    }
+
+    /// Match of the group called `name`, `None` if there's no such group or if it didn't
+    /// participate in the match
+    pub fn name(&self, name: &str) -> Option<Match<'h>> {
+        let index = self
+            .names
+            .iter()
+            .position(|group_name| group_name.as_deref() == Some(name))?;
+
+        self.get(index)
+    }
+
+    /// Match of every group by group number, starting with the whole match
+    pub fn iter(&self) -> impl Iterator<Item = Option<Match<'h>>> + '_ {
+        (0..self.spans.len()).map(|index| self.get(index))
+    }
+}
+
+/// Text matched by the group with number `index`. Panics if the group doesn't exist or
+/// didn't participate in the match, use `Captures::get` to handle those.
+impl Index<usize> for Captures<'_> {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &str {
+        self.get(index)
+            .map(|group| group.as_str())
+            .unwrap_or_else(|| panic!("no match for group {index}"))
+    }
+}
+
+/// Text matched by the group called `name`. Panics if the group doesn't exist or didn't
+/// participate in the match, use `Captures::name` to handle those.
+impl Index<&str> for Captures<'_> {
+    type Output = str;
+
+    fn index(&self, name: &str) -> &str {
+        self.name(name)
+            .map(|group| group.as_str())
+            .unwrap_or_else(|| panic!("no match for group '{name}'"))
+    }
+}
+
+/// Iterator over the groups of every match of `Regex::captures_iter`
+pub struct CaptureMatches<'r, 'h> {
+    searches: Searches<'r, 'h>,
+    names: Arc<[Option<String>]>,
+}
+
+impl<'r, 'h> CaptureMatches<'r, 'h> {
+    pub(crate) fn new(searches: Searches<'r, 'h>, names: Arc<[Option<String>]>) -> Self {
+        Self { searches, names }
+    }
+}
+
+impl<'h> Iterator for CaptureMatches<'_, 'h> {
+    type Item = Captures<'h>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let spans = self.searches.next()?;
+
+        Some(Captures::new(
+            self.searches.input,
+            spans,
+            self.names.clone(),
+        ))
+    }
+}
+
+/// Iterator over the text between matches of `Regex::split`
+pub struct Split<'r, 'h> {
+    matches: Matches<'r, 'h>,
+    last_match_end: usize,
+    finished: bool,
+}
+
+impl<'r, 'h> Split<'r, 'h> {
+    pub(crate) fn new(regex: &'r Regex, input: &'h str) -> Self {
+        Self {
+            matches: Matches::new(regex, input),
+            last_match_end: 0,
+            finished: false,
+        }
+    }
+
+    /// Everything after the last match returned so far, ending the iteration
+    fn rest(&mut self) -> Option<&'h str> {
+        if self.finished {
+            return None;
+        }
+
+        self.finished = true;
+        Some(&self.matches.searches.input[self.last_match_end..])
+    }
+}
+
+impl<'h> Iterator for Split<'_, 'h> {
+    type Item = &'h str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let Some(found) = self.matches.next() else {
+            return self.rest();
+        };
+
+        let piece = &self.matches.searches.input[self.last_match_end..found.start()];
+        self.last_match_end = found.end();
+        Some(piece)
+    }
+}
+
+/// Iterator over the text between matches of `Regex::splitn`
+pub struct SplitN<'r, 'h> {
+    split: Split<'r, 'h>,
+    remaining: usize,
+}
+
+impl<'r, 'h> SplitN<'r, 'h> {
+    pub(crate) fn new(regex: &'r Regex, input: &'h str, limit: usize) -> Self {
+        Self {
+            split: Split::new(regex, input),
+            remaining: limit,
+        }
+    }
+}
+
+impl<'h> Iterator for SplitN<'_, 'h> {
+    type Item = &'h str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        if self.remaining == 0 {
+            return self.split.rest();
+        }
+
+        self.split.next()
+    }
+}
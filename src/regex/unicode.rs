@@ -0,0 +1,363 @@
+use crate::regex::unicode::GeneralCategory::*;
+pub use crate::regex::unicode_tables::Script;
+use crate::regex::unicode_tables::{
+    ALPHABETIC_RANGES, CASE_MAPPINGS, CONNECTOR_PUNCTUATION, DECIMAL_DIGIT_RUN_STARTS,
+    GENERAL_CATEGORY_RUNS, GENERAL_CATEGORY_RUN_STARTS, LOWERCASE_RANGES, SCRIPT_NAMES,
+    SCRIPT_RUNS, SCRIPT_RUN_STARTS, UPPERCASE_RANGES, WHITE_SPACE_RANGES,
+};
+
+/// General categories of chars, `Cn` for unassigned ones
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GeneralCategory {
+    Lu,
+    Ll,
+    Lt,
+    Lm,
+    Lo,
+    Mn,
+    Mc,
+    Me,
+    Nd,
+    Nl,
+    No,
+    Pc,
+    Pd,
+    Ps,
+    Pe,
+    Pi,
+    Pf,
+    Po,
+    Sm,
+    Sc,
+    Sk,
+    So,
+    Zs,
+    Zl,
+    Zp,
+    Cc,
+    Cf,
+    Cs,
+    Co,
+    Cn,
+}
+
+/// Loose names of every general category and group of them, along with what they contain
+const GENERAL_CATEGORY_NAMES: [(&[&str], &[GeneralCategory]); 38] = [
+    (&["l", "letter"], &[Lu, Ll, Lt, Lm, Lo]),
+    (&["lc", "l&", "casedletter"], &[Lu, Ll, Lt]),
+    (&["lu", "uppercaseletter"], &[Lu]),
+    (&["ll", "lowercaseletter"], &[Ll]),
+    (&["lt", "titlecaseletter"], &[Lt]),
+    (&["lm", "modifierletter"], &[Lm]),
+    (&["lo", "otherletter"], &[Lo]),
+    (&["m", "mark", "combiningmark"], &[Mn, Mc, Me]),
+    (&["mn", "nonspacingmark"], &[Mn]),
+    (&["mc", "spacingmark"], &[Mc]),
+    (&["me", "enclosingmark"], &[Me]),
+    (&["n", "number"], &[Nd, Nl, No]),
+    (&["nd", "decimalnumber", "digit"], &[Nd]),
+    (&["nl", "letternumber"], &[Nl]),
+    (&["no", "othernumber"], &[No]),
+    (
+        &["p", "punctuation", "punct"],
+        &[Pc, Pd, Ps, Pe, Pi, Pf, Po],
+    ),
+    (&["pc", "connectorpunctuation"], &[Pc]),
+    (&["pd", "dashpunctuation"], &[Pd]),
+    (&["ps", "openpunctuation"], &[Ps]),
+    (&["pe", "closepunctuation"], &[Pe]),
+    (&["pi", "initialpunctuation"], &[Pi]),
+    (&["pf", "finalpunctuation"], &[Pf]),
+    (&["po", "otherpunctuation"], &[Po]),
+    (&["s", "symbol"], &[Sm, Sc, Sk, So]),
+    (&["sm", "mathsymbol"], &[Sm]),
+    (&["sc", "currencysymbol"], &[Sc]),
+    (&["sk", "modifiersymbol"], &[Sk]),
+    (&["so", "othersymbol"], &[So]),
+    (&["z", "separator"], &[Zs, Zl, Zp]),
+    (&["zs", "spaceseparator"], &[Zs]),
+    (&["zl", "lineseparator"], &[Zl]),
+    (&["zp", "paragraphseparator"], &[Zp]),
+    (&["c", "other"], &[Cc, Cf, Cs, Co, Cn]),
+    (&["cc", "control", "cntrl"], &[Cc]),
+    (&["cf", "format"], &[Cf]),
+    (&["cs", "surrogate"], &[Cs]),
+    (&["co", "privateuse"], &[Co]),
+    (&["cn", "unassigned"], &[Cn]),
+];
+
+/// Classes usable with `\p{...}` and `\P{...}`, or `\pL` for one-letter names: general
+/// categories, scripts and a few binary properties
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnicodeProperty {
+    /// General categories like `Lu`, or groups of them like `L`, one bit per
+    /// `GeneralCategory`
+    GeneralCategories(u32),
+    Alphabetic,
+    Uppercase,
+    Lowercase,
+    WhiteSpace,
+    Script(Script),
+}
+
+impl UnicodeProperty {
+    pub fn general_categories(categories: &[GeneralCategory]) -> Self {
+        UnicodeProperty::GeneralCategories(
+            categories
+                .iter()
+                .fold(0, |bits, category| bits | 1 << *category as u32),
+        )
+    }
+
+    /// Names are compared loosely: case, spaces, underscores and hyphens are ignored.
+    /// Scripts can also be named by their four-letter code, like `Grek`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name: String = name
+            .chars()
+            .filter(|name_char| !matches!(name_char, ' ' | '_' | '-'))
+            .map(|name_char| name_char.to_ascii_lowercase())
+            .collect();
+
+        if let Some((_, categories)) = GENERAL_CATEGORY_NAMES
+            .iter()
+            .find(|(names, _)| names.contains(&name.as_str()))
+        {
+            return Some(Self::general_categories(categories));
+        }
+
+        let property = match name.as_str() {
+            "alphabetic" | "alpha" => UnicodeProperty::Alphabetic,
+            "uppercase" | "upper" => UnicodeProperty::Uppercase,
+            "lowercase" | "lower" => UnicodeProperty::Lowercase,
+            "whitespace" | "wspace" | "space" => UnicodeProperty::WhiteSpace,
+            _ => {
+                let index = SCRIPT_NAMES
+                    .binary_search_by_key(&name.as_str(), |(script_name, _)| script_name)
+                    .ok()?;
+                UnicodeProperty::Script(SCRIPT_NAMES[index].1)
+            }
+        };
+
+        Some(property)
+    }
+
+    pub fn matches_char(&self, input_char: char) -> bool {
+        match self {
+            UnicodeProperty::GeneralCategories(bits) => {
+                bits & (1 << general_category(input_char) as u32) != 0
+            }
+            UnicodeProperty::Alphabetic => is_in_ranges(&ALPHABETIC_RANGES, input_char),
+            UnicodeProperty::Uppercase => is_in_ranges(&UPPERCASE_RANGES, input_char),
+            UnicodeProperty::Lowercase => is_in_ranges(&LOWERCASE_RANGES, input_char),
+            UnicodeProperty::WhiteSpace => is_white_space(input_char),
+            UnicodeProperty::Script(script) => script_of(input_char) == *script,
+        }
+    }
+}
+
+/// General category of `input_char`, as of the tables' Unicode version
+pub fn general_category(input_char: char) -> GeneralCategory {
+    let code_point = input_char as u32;
+    let run = GENERAL_CATEGORY_RUN_STARTS.partition_point(|&start| start <= code_point);
+
+    GENERAL_CATEGORY_RUNS[run - 1]
+}
+
+/// Script of `input_char`, `Common` for chars shared by scripts like digits
+pub fn script_of(input_char: char) -> Script {
+    let code_point = input_char as u32;
+    let run = SCRIPT_RUN_STARTS.partition_point(|&start| start <= code_point);
+
+    SCRIPT_RUNS[run - 1]
+}
+
+/// `\d` chars, decimal digits of any script
+pub fn is_decimal_digit(input_char: char) -> bool {
+    if input_char.is_ascii() {
+        return input_char.is_ascii_digit();
+    }
+
+    let code_point = input_char as u32;
+    let run = DECIMAL_DIGIT_RUN_STARTS.partition_point(|&start| start <= code_point);
+
+    run > 0 && code_point - DECIMAL_DIGIT_RUN_STARTS[run - 1] < 10
+}
+
+/// `\w` chars: letters, decimal digits and connector punctuation like the underscore
+pub fn is_word_char(input_char: char) -> bool {
+    if input_char.is_ascii() {
+        return input_char.is_ascii_alphanumeric() || input_char == '_';
+    }
+
+    is_in_ranges(&ALPHABETIC_RANGES, input_char)
+        || is_decimal_digit(input_char)
+        || CONNECTOR_PUNCTUATION.contains(&input_char)
+}
+
+/// `\s` chars, those with the `White_Space` property
+pub fn is_white_space(input_char: char) -> bool {
+    is_in_ranges(&WHITE_SPACE_RANGES, input_char)
+}
+
+/// Whether `input_char` is a letter with case, or a mark taking one like `\u{0345}`, so
+/// that it can match other chars when ignoring case
+pub fn is_cased(input_char: char) -> bool {
+    is_in_ranges(&LOWERCASE_RANGES, input_char)
+        || is_in_ranges(&UPPERCASE_RANGES, input_char)
+        || general_category(input_char) == Lt
+}
+
+/// Whether `input_char` is in one of the sorted, inclusive `ranges`
+fn is_in_ranges(ranges: &[(u32, u32)], input_char: char) -> bool {
+    let code_point = input_char as u32;
+    let range = ranges.partition_point(|&(start, _)| start <= code_point);
+
+    range > 0 && code_point <= ranges[range - 1].1
+}
+
+/// Chars equal to `input_char` under simple case folding: the char itself, its lowercase
+/// and uppercase forms, and theirs in turn for chars like `ſ` whose uppercase `S` has
+/// another lowercase. Forms longer than a char, like the uppercase `SS` of `ß`, are left
+/// out.
+pub fn case_variants(input_char: char) -> impl Iterator<Item = char> {
+    let (lowercase, uppercase) = case_forms(input_char);
+
+    [
+        input_char,
+        lowercase,
+        uppercase,
+        case_forms(uppercase).0,
+        case_forms(lowercase).1,
+    ]
+    .into_iter()
+}
+
+/// Lowercase and uppercase forms of `input_char`, itself where it has none of a single char
+fn case_forms(input_char: char) -> (char, char) {
+    let code_point = input_char as u32;
+
+    match CASE_MAPPINGS.binary_search_by_key(&code_point, |&(mapped, _, _)| mapped) {
+        Ok(index) => {
+            let (_, lowercase, uppercase) = CASE_MAPPINGS[index];
+            let form = |code_point| char::from_u32(code_point).expect("forms are chars");
+
+            (form(lowercase), form(uppercase))
+        }
+        Err(_) => (input_char, input_char),
+    }
+}
+
+#[cfg(test)]
+mod unicode_tests {
+    use crate::regex::unicode::*;
+
+    #[test]
+    fn decimal_digit_runs_are_sorted_numbers() {
+        assert!(DECIMAL_DIGIT_RUN_STARTS
+            .windows(2)
+            .all(|runs| runs[0] + 10 <= runs[1]));
+
+        for start in DECIMAL_DIGIT_RUN_STARTS {
+            for code_point in start..start + 10 {
+                let digit = char::from_u32(code_point).unwrap();
+                assert_eq!(GeneralCategory::Nd, general_category(digit));
+            }
+        }
+    }
+
+    #[test]
+    fn properties_agree_on_chars_unassigned_in_tables() {
+        // Kawi letter and digit, from Unicode 15
+        let letter = UnicodeProperty::from_name("L").unwrap();
+
+        assert!(!letter.matches_char('\u{11F04}'));
+        assert!(!UnicodeProperty::Alphabetic.matches_char('\u{11F04}'));
+        assert!(!is_word_char('\u{11F04}'));
+        assert!(!is_decimal_digit('\u{11F50}'));
+    }
+
+    #[test]
+    fn chars_with_case_forms_are_cased() {
+        for (code_point, lowercase, uppercase) in CASE_MAPPINGS {
+            for form in [code_point, lowercase, uppercase] {
+                assert!(is_cased(char::from_u32(form).unwrap()));
+            }
+        }
+    }
+
+    #[test]
+    fn decimal_digits() {
+        assert!(is_decimal_digit('7'));
+        assert!(is_decimal_digit('٣'));
+        assert!(is_decimal_digit('९'));
+        assert!(!is_decimal_digit('²'));
+        assert!(!is_decimal_digit('a'));
+        assert!(!is_decimal_digit('\u{0171}'));
+    }
+
+    #[test]
+    fn word_chars() {
+        assert!(is_word_char('ű'));
+        assert!(is_word_char('ж'));
+        assert!(is_word_char('‿'));
+        assert!(!is_word_char('-'));
+        assert!(!is_word_char('€'));
+    }
+
+    #[test]
+    fn case_variants_fold_long_s() {
+        assert!(case_variants('ſ').any(|variant| variant == 's'));
+        assert!(case_variants('Ω').any(|variant| variant == 'ω'));
+    }
+
+    #[test]
+    fn property_names_are_loose() {
+        assert_eq!(
+            Some(UnicodeProperty::general_categories(&[GeneralCategory::Lu])),
+            UnicodeProperty::from_name("Uppercase_Letter")
+        );
+        assert_eq!(
+            Some(UnicodeProperty::Script(Script::Greek)),
+            UnicodeProperty::from_name("greek")
+        );
+        assert_eq!(
+            Some(UnicodeProperty::Script(Script::OldItalic)),
+            UnicodeProperty::from_name("Ital")
+        );
+        assert_eq!(None, UnicodeProperty::from_name("Klingon"));
+    }
+
+    #[test]
+    fn tables_are_sorted() {
+        assert!(GENERAL_CATEGORY_RUN_STARTS
+            .windows(2)
+            .all(|runs| runs[0] < runs[1]));
+        assert!(SCRIPT_RUN_STARTS.windows(2).all(|runs| runs[0] < runs[1]));
+        assert!(SCRIPT_NAMES.windows(2).all(|names| names[0].0 < names[1].0));
+    }
+
+    #[test]
+    fn general_categories() {
+        assert_eq!(GeneralCategory::Lu, general_category('A'));
+        assert_eq!(GeneralCategory::Lt, general_category('ǅ'));
+        assert_eq!(GeneralCategory::Nl, general_category('Ⅻ'));
+        assert_eq!(GeneralCategory::Mn, general_category('\u{0301}'));
+        assert_eq!(GeneralCategory::Sc, general_category('€'));
+        assert_eq!(GeneralCategory::Co, general_category('\u{E000}'));
+        assert_eq!(GeneralCategory::Cn, general_category('\u{10FFFF}'));
+
+        let letter = UnicodeProperty::from_name("L").unwrap();
+        assert!(letter.matches_char('ǅ'));
+        assert!(!letter.matches_char('Ⅻ'));
+        assert!(UnicodeProperty::from_name("N").unwrap().matches_char('Ⅻ'));
+    }
+
+    #[test]
+    fn scripts() {
+        assert_eq!(Script::Greek, script_of('λ'));
+        assert_eq!(Script::Latin, script_of('ǅ'));
+        assert_eq!(Script::Common, script_of('1'));
+        assert_eq!(Script::Inherited, script_of('\u{0301}'));
+        assert_eq!(Script::Unknown, script_of('\u{10FFFF}'));
+    }
+}
@@ -0,0 +1,259 @@
+use crate::args::Config;
+use crate::glob::Glob;
+use crate::grep::STDIN_NAME;
+use codecrafters_grep::RegexParsingError;
+use std::fs;
+use std::fs::{DirEntry, Metadata};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::vec;
+use thiserror::Error;
+
+/// How directories are searched
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Recursion {
+    /// Directories are errors, like any file that can't be read
+    #[default]
+    Off,
+    /// `-r`, searching the files of directories, following symlinks only when they are
+    /// operands
+    Recursive,
+    /// `-R`, following every symlink
+    FollowSymlinks,
+}
+
+#[derive(Debug, Error)]
+#[error("invalid glob '{0}': {1}")]
+pub struct InvalidGlob(String, RegexParsingError);
+
+/// What walking an operand finds
+pub enum WalkEntry {
+    Stdin,
+    /// File to search, along with its name as it's printed
+    File(PathBuf, String),
+    /// Reading a directory or looking at one of its entries failed
+    Error(String, io::Error),
+    /// Directory found again inside itself through symlinks, which is skipped
+    Loop(String),
+}
+
+/// Finds the files to search for every operand, according to the recursion and file
+/// filters of the command line
+pub struct Walker {
+    recursion: Recursion,
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    exclude_dir: Vec<Glob>,
+}
+
+impl Walker {
+    pub fn new(config: &Config) -> Result<Self, InvalidGlob> {
+        let compile = |globs: &[String]| -> Result<Vec<Glob>, InvalidGlob> {
+            globs
+                .iter()
+                .map(|glob| Glob::new(glob).map_err(|error| InvalidGlob(glob.clone(), error)))
+                .collect()
+        };
+
+        Ok(Self {
+            recursion: config.recursion,
+            include: compile(&config.include)?,
+            exclude: compile(&config.exclude)?,
+            exclude_dir: compile(&config.exclude_dir)?,
+        })
+    }
+
+    /// Walks `operand`, or the working directory for `None`, naming what it contains
+    /// without a `./` prefix
+    pub fn walk(&self, operand: Option<&Path>) -> Walk<'_> {
+        Walk {
+            walker: self,
+            operand: Some(operand.map(Path::to_path_buf)),
+            directories: Vec::new(),
+        }
+    }
+
+    /// Filters apply to the name of the file itself, not to the directories above it
+    fn is_file_included(&self, path: &Path) -> bool {
+        let Some(name) = file_name(path) else {
+            return true;
+        };
+
+        !self.exclude.iter().any(|glob| glob.matches(&name))
+            && (self.include.is_empty() || self.include.iter().any(|glob| glob.matches(&name)))
+    }
+
+    fn is_dir_excluded(&self, path: &Path) -> bool {
+        file_name(path).is_some_and(|name| self.exclude_dir.iter().any(|glob| glob.matches(&name)))
+    }
+}
+
+/// Iterator over what's found in one operand, depth first and sorted by name within each
+/// directory
+pub struct Walk<'w> {
+    walker: &'w Walker,
+    /// The operand, until it's been looked at
+    operand: Option<Option<PathBuf>>,
+    /// Directories being walked, innermost last
+    directories: Vec<Directory>,
+}
+
+struct Directory {
+    name: String,
+    entries: vec::IntoIter<DirEntry>,
+    /// Device and inode number, for detecting loops
+    id: Option<(u64, u64)>,
+}
+
+impl Iterator for Walk<'_> {
+    type Item = WalkEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let found = match self.operand.take() {
+                Some(operand) => self.visit_operand(operand),
+                None => self.visit_next_entry()?,
+            };
+
+            if found.is_some() {
+                return found;
+            }
+        }
+    }
+}
+
+impl Walk<'_> {
+    fn visit_operand(&mut self, operand: Option<PathBuf>) -> Option<WalkEntry> {
+        let Some(path) = operand else {
+            return self.enter_directory(PathBuf::from("."), String::new());
+        };
+
+        if path.as_os_str() == STDIN_NAME {
+            return Some(WalkEntry::Stdin);
+        }
+
+        // Names are only printed, paths that aren't valid UTF-8 are still opened as is
+        let name = path.to_string_lossy().into_owned();
+
+        // Without recursion, directories are reported when they fail to be searched
+        if self.walker.recursion == Recursion::Off {
+            return self
+                .walker
+                .is_file_included(&path)
+                .then_some(WalkEntry::File(path, name));
+        }
+
+        // Symlinks given as operands are followed either way
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => {
+                if self.walker.is_dir_excluded(&path) {
+                    return None;
+                }
+                self.enter_directory(path, name)
+            }
+            Ok(_) => self
+                .walker
+                .is_file_included(&path)
+                .then_some(WalkEntry::File(path, name)),
+            Err(error) => Some(WalkEntry::Error(name, error)),
+        }
+    }
+
+    /// Looks at the next entry of the innermost directory, `None` when every directory
+    /// has been walked
+    fn visit_next_entry(&mut self) -> Option<Option<WalkEntry>> {
+        let directory = self.directories.last_mut()?;
+
+        let Some(entry) = directory.entries.next() else {
+            self.directories.pop();
+            return Some(None);
+        };
+
+        let path = entry.path();
+        let name = child_name(&directory.name, &entry.file_name().to_string_lossy());
+
+        let file_type = match entry.file_type() {
+            Ok(file_type) if file_type.is_symlink() => {
+                if self.walker.recursion != Recursion::FollowSymlinks {
+                    return Some(None);
+                }
+
+                match fs::metadata(&path) {
+                    Ok(metadata) => metadata.file_type(),
+                    Err(error) => return Some(Some(WalkEntry::Error(name, error))),
+                }
+            }
+            Ok(file_type) => file_type,
+            Err(error) => return Some(Some(WalkEntry::Error(name, error))),
+        };
+
+        // Devices, FIFOs and sockets found while walking are skipped
+        let found = if file_type.is_dir() {
+            if self.walker.is_dir_excluded(&path) {
+                None
+            } else {
+                self.enter_directory(path, name)
+            }
+        } else if file_type.is_file() && self.walker.is_file_included(&path) {
+            Some(WalkEntry::File(path, name))
+        } else {
+            None
+        };
+
+        Some(found)
+    }
+
+    /// Starts walking the directory at `path`, unless it's one of the directories it's in
+    fn enter_directory(&mut self, path: PathBuf, name: String) -> Option<WalkEntry> {
+        let id = match fs::metadata(&path) {
+            Ok(metadata) => file_id(&metadata),
+            Err(error) => return Some(WalkEntry::Error(name, error)),
+        };
+
+        if id.is_some() && self.directories.iter().any(|directory| directory.id == id) {
+            return Some(WalkEntry::Loop(name));
+        }
+
+        let entries =
+            fs::read_dir(&path).and_then(|entries| entries.collect::<io::Result<Vec<_>>>());
+        let mut entries = match entries {
+            Ok(entries) => entries,
+            Err(error) => return Some(WalkEntry::Error(name, error)),
+        };
+        entries.sort_by_key(DirEntry::file_name);
+
+        self.directories.push(Directory {
+            name,
+            entries: entries.into_iter(),
+            id,
+        });
+        None
+    }
+}
+
+/// Name of the entry called `entry_name` in the directory named `parent`, which is empty
+/// for the working directory when no operand is given
+fn child_name(parent: &str, entry_name: &str) -> String {
+    if parent.is_empty() || parent.ends_with('/') {
+        format!("{parent}{entry_name}")
+    } else {
+        format!("{parent}/{entry_name}")
+    }
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    Some(path.file_name()?.to_string_lossy().into_owned())
+}
+
+#[cfg(unix)]
+fn file_id(metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((metadata.dev(), metadata.ino()))
+}
+
+/// Without inode numbers, loops aren't detected
+#[cfg(not(unix))]
+fn file_id(_metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
@@ -0,0 +1,442 @@
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Runs the binary with `args`, feeding it `stdin`
+fn grep(args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_codecrafters-grep"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    // Grep may exit without reading its input, e.g. for an invalid pattern
+    if let Err(error) = child.stdin.take().unwrap().write_all(stdin) {
+        assert_eq!(ErrorKind::BrokenPipe, error.kind());
+    }
+    child.wait_with_output().unwrap()
+}
+
+/// Runs the binary with `args` in `dir`, without input
+fn grep_in(dir: &Path, args: &[impl AsRef<OsStr>]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_codecrafters-grep"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap()
+}
+
+/// Fresh directory for the files of one test, removed with everything in it when dropped
+struct TestDir(PathBuf);
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn test_dir(name: &str) -> TestDir {
+    let dir = std::env::temp_dir().join(format!("grep_cli_test_{}_{name}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    TestDir(dir)
+}
+
+#[cfg(test)]
+mod files_tests {
+    use crate::{grep, test_dir};
+    use std::fs;
+
+    #[test]
+    fn stdin_matching_line_is_printed() {
+        let output = grep(&["-E", "b+"], b"abc\nxyz\nbb\n");
+
+        assert_eq!(Some(0), output.status.code());
+        assert_eq!(b"abc\nbb\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn stdin_without_match_exits_with_1() {
+        let output = grep(&["-E", "q"], b"abc\n");
+
+        assert_eq!(Some(1), output.status.code());
+        assert!(output.stdout.is_empty());
+    }
+
+    #[test]
+    fn pattern_without_e_flag() {
+        let output = grep(&["^x"], b"xy\n");
+        assert_eq!(Some(0), output.status.code())
+    }
+
+    #[test]
+    fn last_line_without_newline() {
+        let output = grep(&["c$"], b"ab\nabc");
+
+        assert_eq!(b"abc\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn invalid_utf8_input_is_searched() {
+        let output = grep(&["caf."], b"caf\xE9\n");
+
+        assert_eq!(Some(0), output.status.code());
+        assert_eq!(b"caf\xE9\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn invalid_pattern_exits_with_2() {
+        let output = grep(&["a(?q)"], b"a\n");
+
+        assert_eq!(Some(2), output.status.code());
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("grep: "));
+    }
+
+    #[test]
+    fn unbalanced_parentheses_exit_with_2() {
+        let output = grep(&["-x", "ab)"], b"ab\n");
+
+        assert_eq!(Some(2), output.status.code());
+        assert!(output.stdout.is_empty());
+        assert_eq!(Some(2), grep(&["(ab"], b"ab\n").status.code());
+    }
+
+    #[test]
+    fn missing_pattern_exits_with_2() {
+        assert_eq!(Some(2), grep(&[], b"").status.code())
+    }
+
+    #[test]
+    fn single_file_lines_without_name() {
+        let dir = test_dir("single_file");
+        let file = dir.join("a.txt");
+        fs::write(&file, "one\ntwo\nthree\n").unwrap();
+
+        let output = grep(&["t", file.to_str().unwrap()], b"");
+
+        assert_eq!(Some(0), output.status.code());
+        assert_eq!(b"two\nthree\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn multiple_files_lines_with_names() {
+        let dir = test_dir("multiple_files");
+        let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+        fs::write(&a, "apple\n").unwrap();
+        fs::write(&b, "banana\napricot\n").unwrap();
+
+        let output = grep(&["^ap", a.to_str().unwrap(), b.to_str().unwrap()], b"");
+
+        let expected = format!("{}:apple\n{}:apricot\n", a.display(), b.display());
+        assert_eq!(expected.as_bytes(), output.stdout.as_slice());
+    }
+
+    #[test]
+    fn dash_reads_stdin_among_files() {
+        let dir = test_dir("dash");
+        let file = dir.join("a.txt");
+        fs::write(&file, "from file\n").unwrap();
+
+        let output = grep(&["from", file.to_str().unwrap(), "-"], b"from stdin\n");
+
+        let expected = format!(
+            "{}:from file\n(standard input):from stdin\n",
+            file.display()
+        );
+        assert_eq!(expected.as_bytes(), output.stdout.as_slice());
+    }
+
+    #[test]
+    fn missing_file_exits_with_2_after_searching_others() {
+        let dir = test_dir("missing_file");
+        let file = dir.join("a.txt");
+        fs::write(&file, "match\n").unwrap();
+        let missing = dir.join("missing.txt");
+
+        let output = grep(
+            &["match", missing.to_str().unwrap(), file.to_str().unwrap()],
+            b"",
+        );
+
+        assert_eq!(Some(2), output.status.code());
+        assert!(!output.stdout.is_empty());
+        assert_eq!(
+            format!("grep: {}: No such file or directory\n", missing.display()),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_file_name_is_searched() {
+        use crate::grep_in;
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = test_dir("non_utf8_name");
+        let name = OsStr::from_bytes(b"\xFF.txt");
+        fs::write(dir.join(name), "ab\n").unwrap();
+
+        let output = grep_in(&dir, &[OsStr::new("-H"), OsStr::new("ab"), name]);
+
+        assert_eq!(Some(0), output.status.code());
+        assert_eq!("\u{FFFD}.txt:ab\n".as_bytes(), output.stdout.as_slice());
+    }
+
+    #[test]
+    fn directory_without_recursion_is_an_error() {
+        let dir = test_dir("directory");
+
+        let output = grep(&["a", dir.to_str().unwrap()], b"");
+
+        assert_eq!(Some(2), output.status.code());
+        assert_eq!(
+            format!("grep: {}: Is a directory\n", dir.display()),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
+
+#[cfg(test)]
+mod options_tests {
+    use crate::{grep, test_dir};
+    use std::fs;
+
+    #[test]
+    fn combined_flags() {
+        let output = grep(&["-inv", "X"], b"x\ny\nX\nz\n");
+
+        assert_eq!(b"2:y\n4:z\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn long_options() {
+        let output = grep(&["--ignore-case", "--count", "A"], b"a\nb\nA\n");
+
+        assert_eq!(b"2\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn repeated_regexp_options() {
+        let output = grep(&["-e", "one", "--regexp=three"], b"one\ntwo\nthree\n");
+
+        assert_eq!(b"one\nthree\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn double_dash_ends_options() {
+        let output = grep(&["--", "-v"], b"a\n-v\n");
+
+        assert_eq!(b"-v\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn patterns_from_file() {
+        let dir = test_dir("pattern_file");
+        let patterns = dir.join("patterns.txt");
+        fs::write(&patterns, "^b\nd$\n").unwrap();
+
+        let output = grep(&["-f", patterns.to_str().unwrap()], b"abc\nbcd\nxd\n");
+
+        assert_eq!(b"bcd\nxd\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn pattern_file_lines_keep_carriage_returns() {
+        let dir = test_dir("pattern_file_crlf");
+        let patterns = dir.join("patterns.txt");
+        fs::write(&patterns, "^a\r$\n").unwrap();
+
+        let output = grep(&["-f", patterns.to_str().unwrap()], b"a\na\r\n");
+
+        assert_eq!(b"a\r\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn missing_pattern_file_exits_with_2_without_usage() {
+        let dir = test_dir("missing_pattern_file");
+        let patterns = dir.join("missing.txt");
+
+        let output = grep(&["-f", patterns.to_str().unwrap()], b"a\n");
+
+        assert_eq!(Some(2), output.status.code());
+        assert_eq!(
+            format!("grep: {}: No such file or directory\n", patterns.display()),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn word_and_line_regexp() {
+        assert_eq!(
+            b"foo bar\n",
+            grep(&["-w", "foo"], b"foo bar\nfoobar\n").stdout.as_slice()
+        );
+        assert_eq!(
+            b"foo\n",
+            grep(&["-x", "fo+"], b"foo\nfoo bar\n").stdout.as_slice()
+        );
+    }
+
+    #[test]
+    fn word_regexp_doesnt_backtrack() {
+        let input = format!("{} c\n", "a".repeat(40));
+        let output = grep(&["-w", "(a|aa)*c"], input.as_bytes());
+
+        assert_eq!(input.as_bytes(), output.stdout.as_slice());
+    }
+
+    #[test]
+    fn files_with_matches_and_count_with_names() {
+        let dir = test_dir("list_files");
+        let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+        fs::write(&a, "x\nx\n").unwrap();
+        fs::write(&b, "y\n").unwrap();
+        let files = [a.to_str().unwrap(), b.to_str().unwrap()];
+
+        let output = grep(&["-l", "x", files[0], files[1]], b"");
+        assert_eq!(format!("{}\n", a.display()).as_bytes(), output.stdout);
+
+        let output = grep(&["-c", "x", files[0], files[1]], b"");
+        let expected = format!("{}:2\n{}:0\n", a.display(), b.display());
+        assert_eq!(expected.as_bytes(), output.stdout);
+    }
+
+    #[test]
+    fn quiet_and_no_messages() {
+        let output = grep(&["-qs", "a", "/nonexistent/file", "-"], b"a\n");
+
+        assert_eq!(Some(0), output.status.code());
+        assert!(output.stdout.is_empty() && output.stderr.is_empty());
+    }
+
+    #[test]
+    fn help_and_version_exit_with_0() {
+        let help = grep(&["--help"], b"");
+        assert_eq!(Some(0), help.status.code());
+        assert!(String::from_utf8_lossy(&help.stdout).starts_with("Usage: grep"));
+
+        let version = grep(&["--version"], b"");
+        assert_eq!(Some(0), version.status.code());
+        assert!(String::from_utf8_lossy(&version.stdout).starts_with("grep "));
+    }
+
+    #[test]
+    fn invalid_option_exits_with_2() {
+        let output = grep(&["-k", "a"], b"a\n");
+
+        assert_eq!(Some(2), output.status.code());
+        assert!(String::from_utf8_lossy(&output.stderr).starts_with("grep: invalid option -- 'k'"));
+    }
+}
+
+#[cfg(test)]
+mod recursive_tests {
+    use crate::{grep_in, test_dir, TestDir};
+    use std::fs;
+
+    /// `dir/a.rs`, `dir/sub/b.md` and `dir/.git/c`, all containing `hit`
+    fn tree(name: &str) -> TestDir {
+        let dir = test_dir(name);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join("a.rs"), "hit\n").unwrap();
+        fs::write(dir.join("sub/b.md"), "hit\nmiss\n").unwrap();
+        fs::write(dir.join(".git/c"), "hit\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn working_directory_without_operands() {
+        let dir = tree("working_directory");
+
+        let output = grep_in(&dir, &["-r", "hit"]);
+
+        assert_eq!(Some(0), output.status.code());
+        assert_eq!(
+            b".git/c:hit\na.rs:hit\nsub/b.md:hit\n",
+            output.stdout.as_slice()
+        );
+    }
+
+    #[test]
+    fn directory_operand_prefixes_names() {
+        let dir = tree("directory_operand");
+
+        let output = grep_in(&dir, &["-rn", "hit", "sub"]);
+
+        assert_eq!(b"sub/b.md:1:hit\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn single_file_operand_without_name() {
+        let dir = tree("single_file_operand");
+
+        let output = grep_in(&dir, &["-r", "hit", "a.rs"]);
+
+        assert_eq!(b"hit\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn include_and_exclude_filters() {
+        let dir = tree("filters");
+
+        let output = grep_in(&dir, &["-r", "--include=*.rs", "--include=*.md", "hit"]);
+        assert_eq!(b"a.rs:hit\nsub/b.md:hit\n", output.stdout.as_slice());
+
+        let output = grep_in(&dir, &["-r", "--exclude=*.rs", "--exclude-dir=.git", "hit"]);
+        assert_eq!(b"sub/b.md:hit\n", output.stdout.as_slice());
+    }
+
+    #[test]
+    fn invalid_glob_exits_with_2() {
+        let dir = tree("invalid_glob");
+
+        let output = grep_in(&dir, &["-r", "--include=[z-a]", "hit"]);
+
+        assert_eq!(Some(2), output.status.code());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_followed_only_with_dereference() {
+        let dir = tree("symlinks");
+        std::os::unix::fs::symlink("../a.rs", dir.join("sub/link.rs")).unwrap();
+
+        let output = grep_in(&dir, &["-r", "hit", "sub"]);
+        assert_eq!(b"sub/b.md:hit\n", output.stdout.as_slice());
+
+        let output = grep_in(&dir, &["-R", "hit", "sub"]);
+        assert_eq!(b"sub/b.md:hit\nsub/link.rs:hit\n", output.stdout.as_slice());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn directory_loops_are_skipped_with_a_warning() {
+        let dir = tree("loop");
+        std::os::unix::fs::symlink("..", dir.join("sub/up")).unwrap();
+
+        let output = grep_in(&dir, &["-R", "miss", "sub"]);
+
+        assert_eq!(Some(0), output.status.code());
+        assert_eq!(b"sub/b.md:miss\n", output.stdout.as_slice());
+        assert_eq!(
+            "grep: sub/up/sub: warning: recursive directory loop\n",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
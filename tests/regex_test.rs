@@ -350,4 +350,1484 @@ mod matches_tests {
         let regex = Regex::new("(([abc]+)-([def]+)) is \\1, not ([^xyz]+), \\2, or \\3").unwrap();
         assert!(regex.matches("abc-def is abc-def, not efg, abc, or def"))
     }
+
+    #[test]
+    fn one_or_more_gives_back_chars_matches() {
+        let regex = Regex::new("a+ab").unwrap();
+        assert!(regex.matches("xaaab"))
+    }
+
+    #[test]
+    fn one_or_more_gives_back_chars_doesnt_match() {
+        let regex = Regex::new("a+ab").unwrap();
+        assert!(!regex.matches("ab"))
+    }
+
+    #[test]
+    fn one_or_more_wildcard_backtracks_to_last_occurrence() {
+        let regex = Regex::new("^.+b$").unwrap();
+        assert!(regex.matches("abab"))
+    }
+
+    #[test]
+    fn optional_retries_skipped_branch() {
+        let regex = Regex::new("colou?ur").unwrap();
+        assert!(regex.matches("colour"))
+    }
+
+    #[test]
+    fn optional_group_retries_skipped_branch() {
+        let regex = Regex::new("^(ab)?abc$").unwrap();
+        assert!(regex.matches("abc"))
+    }
+
+    #[test]
+    fn one_or_more_group_backtracks_into_previous_repetition() {
+        let regex = Regex::new("^(a|ab)+c$").unwrap();
+        assert!(regex.matches("aabc"))
+    }
+
+    #[test]
+    fn backreference_after_one_or_more_backtracks() {
+        let regex = Regex::new("(\\w+) \\1").unwrap();
+        assert!(regex.matches("hello hello"))
+    }
+
+    #[test]
+    fn backreference_after_one_or_more_backtracks_to_shorter_capture() {
+        let regex = Regex::new("^(\\w+)\\1$").unwrap();
+        assert!(regex.matches("abcabc"))
+    }
+
+    #[test]
+    fn backreference_after_one_or_more_doesnt_match() {
+        let regex = Regex::new("^(\\w+)\\1$").unwrap();
+        assert!(!regex.matches("abcab"))
+    }
+
+    #[test]
+    fn nested_quantifiers_on_long_input_dont_match() {
+        let regex = Regex::new("^(a+)+$").unwrap();
+        assert!(!regex.matches(&format!("{}b", "a".repeat(64))))
+    }
+
+    #[test]
+    fn overlapping_alternation_on_long_input_matches() {
+        let regex = Regex::new("(a|aa)+b").unwrap();
+        assert!(regex.matches(&format!("{}b", "a".repeat(64))))
+    }
+
+    #[test]
+    fn overlapping_alternation_on_long_input_doesnt_match() {
+        let regex = Regex::new("(a|aa)+b").unwrap();
+        assert!(!regex.matches(&"a".repeat(64)))
+    }
+
+    #[test]
+    fn exact_repetition_matches() {
+        let regex = Regex::new("\\d{3}-\\d{4}").unwrap();
+        assert!(regex.matches("call 555-1234 now"))
+    }
+
+    #[test]
+    fn exact_repetition_doesnt_match() {
+        let regex = Regex::new("\\d{3}-\\d{4}").unwrap();
+        assert!(!regex.matches("call 55-1234 now"))
+    }
+
+    #[test]
+    fn at_least_repetition_matches() {
+        let regex = Regex::new("^ab{2,}c$").unwrap();
+        assert!(regex.matches("abbbbbc"))
+    }
+
+    #[test]
+    fn at_least_repetition_doesnt_match() {
+        let regex = Regex::new("^ab{2,}c$").unwrap();
+        assert!(!regex.matches("abc"))
+    }
+
+    #[test]
+    fn bounded_repetition_matches() {
+        let regex = Regex::new("^a{2,3}$").unwrap();
+        assert!(regex.matches("aaa"))
+    }
+
+    #[test]
+    fn bounded_repetition_doesnt_match() {
+        let regex = Regex::new("^a{2,3}$").unwrap();
+        assert!(!regex.matches("aaaa"))
+    }
+
+    #[test]
+    fn group_repetition_with_backreference_matches() {
+        let regex = Regex::new("^(\\w{2}){2} \\1$").unwrap();
+        assert!(regex.matches("abcd cd"))
+    }
+
+    #[test]
+    fn zero_or_more_with_missing_char_matches() {
+        let regex = Regex::new("^ab*c$").unwrap();
+        assert!(regex.matches("ac"))
+    }
+
+    #[test]
+    fn zero_or_more_with_repeated_char_matches() {
+        let regex = Regex::new("^ab*c$").unwrap();
+        assert!(regex.matches("abbbc"))
+    }
+
+    #[test]
+    fn zero_or_more_doesnt_match() {
+        let regex = Regex::new("^ab*c$").unwrap();
+        assert!(!regex.matches("abbdc"))
+    }
+
+    #[test]
+    fn zero_or_more_wildcard_matches_quoted_string() {
+        let regex = Regex::new("\"(.*)\"").unwrap();
+        assert!(regex.matches("say \"hi\" and \"bye\""))
+    }
+
+    #[test]
+    fn lazy_zero_or_more_matches() {
+        let regex = Regex::new("\"(.*?)\"$").unwrap();
+        assert!(regex.matches("say \"hi\" and \"bye\""))
+    }
+
+    #[test]
+    fn lazy_one_or_more_expands_when_needed() {
+        let regex = Regex::new("^a+?b$").unwrap();
+        assert!(regex.matches("aaab"))
+    }
+
+    #[test]
+    fn lazy_one_or_more_with_backreference_matches() {
+        let regex = Regex::new("^(a+?)\\1$").unwrap();
+        assert!(regex.matches("aaaa"))
+    }
+
+    #[test]
+    fn lazy_optional_with_backreference_matches() {
+        let regex = Regex::new("^(a??)b\\1$").unwrap();
+        assert!(regex.matches("aba"))
+    }
+
+    #[test]
+    fn lazy_repetition_doesnt_match() {
+        let regex = Regex::new("^a{2,3}?$").unwrap();
+        assert!(!regex.matches("a"))
+    }
+
+    #[test]
+    fn optional_group_matching_empty_with_backreference_matches() {
+        let regex = Regex::new("(a*)?b\\1").unwrap();
+        assert!(regex.matches("b"))
+    }
+
+    #[test]
+    fn optional_group_of_optional_with_backreference_matches() {
+        let regex = Regex::new("(a?)?b\\1").unwrap();
+        assert!(regex.matches("b"))
+    }
+
+    #[test]
+    fn optional_group_matching_no_digits_with_backreference_matches() {
+        let regex = Regex::new("(\\d*)?-\\1").unwrap();
+        assert!(regex.matches("x-"))
+    }
+
+    #[test]
+    fn zero_or_more_group_matching_empty_with_backreference_matches() {
+        let regex = Regex::new("(a*)*b\\1").unwrap();
+        assert!(regex.matches("b"))
+    }
+
+    #[test]
+    fn possessive_zero_or_more_doesnt_give_back() {
+        let regex = Regex::new("^a*+a").unwrap();
+        assert!(!regex.matches("aaaa"))
+    }
+
+    #[test]
+    fn possessive_one_or_more_matches() {
+        let regex = Regex::new("^\\d++-").unwrap();
+        assert!(regex.matches("123-456"))
+    }
+
+    #[test]
+    fn possessive_optional_doesnt_give_back() {
+        let regex = Regex::new("^ab?+b$").unwrap();
+        assert!(!regex.matches("ab"))
+    }
+
+    #[test]
+    fn possessive_repetition_doesnt_give_back() {
+        let regex = Regex::new("^(a|ab){2}+c").unwrap();
+        assert!(!regex.matches("aabc"))
+    }
+
+    #[test]
+    fn greedy_repetition_gives_back_where_possessive_doesnt() {
+        let regex = Regex::new("^(a|ab){2}c").unwrap();
+        assert!(regex.matches("aabc"))
+    }
+
+    #[test]
+    fn atomic_group_doesnt_backtrack_into_alternation() {
+        let regex = Regex::new("^(?>a|ab)c").unwrap();
+        assert!(!regex.matches("abc"))
+    }
+
+    #[test]
+    fn atomic_group_matches() {
+        let regex = Regex::new("^(?>ab|a)c").unwrap();
+        assert!(regex.matches("abc"))
+    }
+
+    #[test]
+    fn atomic_group_keeps_captures() {
+        let regex = Regex::new("^(?>(\\w+)-)\\1$").unwrap();
+        assert!(regex.matches("ab-ab"))
+    }
+
+    #[test]
+    fn possessive_group_on_long_input_doesnt_match() {
+        let regex = Regex::new("(\\w++)+$").unwrap();
+        assert!(!regex.matches(&format!("{}!", "a".repeat(256))))
+    }
+
+    #[test]
+    fn char_range_matches() {
+        let regex = Regex::new("^[a-z]+$").unwrap();
+        assert!(regex.matches("hello"))
+    }
+
+    #[test]
+    fn char_range_doesnt_match() {
+        let regex = Regex::new("^[a-z]+$").unwrap();
+        assert!(!regex.matches("hello-world"))
+    }
+
+    #[test]
+    fn negative_char_range_matches() {
+        let regex = Regex::new("[^0-9a-f]").unwrap();
+        assert!(regex.matches("3fz"))
+    }
+
+    #[test]
+    fn negative_char_range_doesnt_match() {
+        let regex = Regex::new("[^0-9a-f]").unwrap();
+        assert!(!regex.matches("3fa0"))
+    }
+
+    #[test]
+    fn caret_inside_char_group_is_literal() {
+        let regex = Regex::new("[a^]").unwrap();
+        assert!(regex.matches("^"))
+    }
+
+    #[test]
+    fn escaped_closing_bracket_in_char_group_matches() {
+        let regex = Regex::new("[\\]x]+").unwrap();
+        assert!(regex.matches("a]]"))
+    }
+
+    #[test]
+    fn escaped_backslash_in_char_group_matches() {
+        let regex = Regex::new("C:[\\\\/]").unwrap();
+        assert!(regex.matches("C:\\Users"))
+    }
+
+    #[test]
+    fn embedded_classes_in_char_group_match() {
+        let regex = Regex::new("^[\\d\\w_]+$").unwrap();
+        assert!(regex.matches("snake_case_42"))
+    }
+
+    #[test]
+    fn embedded_classes_in_negative_char_group_dont_match() {
+        let regex = Regex::new("[^\\d\\w]").unwrap();
+        assert!(!regex.matches("abc123"))
+    }
+
+    #[test]
+    fn posix_classes_match() {
+        let cases = [
+            ("[[:alnum:]]", "a", "-"),
+            ("[[:alpha:]]", "Z", "1"),
+            ("[[:blank:]]", "\t", "\n"),
+            ("[[:cntrl:]]", "\x07", "a"),
+            ("[[:digit:]]", "7", "x"),
+            ("[[:graph:]]", "~", " "),
+            ("[[:lower:]]", "q", "Q"),
+            ("[[:print:]]", " ", "\x7f"),
+            ("[[:punct:]]", "!", "a"),
+            ("[[:space:]]", "\x0b", "_"),
+            ("[[:upper:]]", "Q", "q"),
+            ("[[:xdigit:]]", "F", "g"),
+        ];
+
+        for (pattern, matching, not_matching) in cases {
+            let regex = Regex::new(pattern).unwrap();
+            assert!(regex.matches(matching), "{pattern} should match {matching:?}");
+            assert!(
+                !regex.matches(not_matching),
+                "{pattern} shouldn't match {not_matching:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn posix_classes_combined_with_chars_match() {
+        let regex = Regex::new("^[[:upper:]_][[:upper:][:digit:]_]*$").unwrap();
+        assert!(regex.matches("MAX_SIZE_2"))
+    }
+
+    #[test]
+    fn negated_posix_class_matches() {
+        let regex = Regex::new("^[^[:digit:]]+$").unwrap();
+        assert!(regex.matches("no digits here"))
+    }
+
+    #[test]
+    fn negated_posix_class_doesnt_match() {
+        let regex = Regex::new("^[^[:digit:]]+$").unwrap();
+        assert!(!regex.matches("version 2"))
+    }
+
+    #[test]
+    fn alphanumeric_class_matches_underscore() {
+        let regex = Regex::new("^\\w+$").unwrap();
+        assert!(regex.matches("snake_case"))
+    }
+
+    #[test]
+    fn whitespace_class_matches() {
+        let regex = Regex::new("a\\sb").unwrap();
+        assert!(regex.matches("a\tb"))
+    }
+
+    #[test]
+    fn whitespace_class_doesnt_match() {
+        let regex = Regex::new("a\\sb").unwrap();
+        assert!(!regex.matches("a_b"))
+    }
+
+    #[test]
+    fn negated_classes_match() {
+        let regex = Regex::new("^\\S+@\\S+$").unwrap();
+        assert!(regex.matches("user@example.com"))
+    }
+
+    #[test]
+    fn negated_classes_dont_match() {
+        let regex = Regex::new("^\\S+@\\S+$").unwrap();
+        assert!(!regex.matches("user @example.com"))
+    }
+
+    #[test]
+    fn not_digit_and_not_alphanumeric_classes_match() {
+        let regex = Regex::new("^\\D\\W$").unwrap();
+        assert!(regex.matches("a-"))
+    }
+
+    #[test]
+    fn not_digit_and_not_alphanumeric_classes_dont_match() {
+        let regex = Regex::new("^\\D\\W$").unwrap();
+        assert!(!regex.matches("1-"))
+    }
+
+    #[test]
+    fn word_boundary_matches() {
+        let regex = Regex::new("\\bfoo\\b").unwrap();
+        assert!(regex.matches("a foo, b"))
+    }
+
+    #[test]
+    fn word_boundary_doesnt_match_inside_word() {
+        let regex = Regex::new("\\bfoo\\b").unwrap();
+        assert!(!regex.matches("foobar food_foo"))
+    }
+
+    #[test]
+    fn not_word_boundary_matches() {
+        let regex = Regex::new("\\Boo\\B").unwrap();
+        assert!(regex.matches("food"))
+    }
+
+    #[test]
+    fn not_word_boundary_doesnt_match() {
+        let regex = Regex::new("\\Bfoo").unwrap();
+        assert!(!regex.matches("foo"))
+    }
+
+    #[test]
+    fn half_word_boundaries_match_around_non_word_chars() {
+        let regex = Regex::new("\\b{start-half}-foo-\\b{end-half}").unwrap();
+        assert!(regex.matches("a -foo- b"));
+        assert!(!regex.matches("a-foo-b"))
+    }
+
+    #[test]
+    fn ascii_only_word_boundary_treats_non_ascii_as_non_word() {
+        let regex = Regex::new("(?-u)\\bfoo\\b").unwrap();
+        assert!(regex.matches("éfooé"));
+        assert!(!Regex::new("\\bfoo\\b").unwrap().matches("éfooé"))
+    }
+
+    #[test]
+    fn ascii_only_not_word_boundary_and_halves_treat_non_ascii_as_non_word() {
+        assert!(!Regex::new("(?-u)\\Bé").unwrap().matches("aé"));
+        assert!(Regex::new("(?-u)é\\b{start-half}").unwrap().matches("é"));
+        assert!(Regex::new("(?-u)\\b{end-half}é").unwrap().matches("é"))
+    }
+
+    #[test]
+    fn ascii_only_word_boundary_with_backreference_matches() {
+        let regex = Regex::new("(?-u)\\b(\\w+)é\\1\\b").unwrap();
+        assert!(regex.matches("abéab"))
+    }
+
+    #[test]
+    fn word_boundary_with_backreference_matches() {
+        let regex = Regex::new("\\b(\\w+) \\1\\b").unwrap();
+        assert!(regex.matches("it is is true"))
+    }
+
+    #[test]
+    fn word_boundary_with_backreference_doesnt_match() {
+        let regex = Regex::new("\\b(\\w+) \\1\\b").unwrap();
+        assert!(!regex.matches("this isn't"))
+    }
+
+    #[test]
+    fn escaped_dot_matches_ip_address() {
+        let regex = Regex::new("^\\d{1,3}\\.\\d{1,3}\\.\\d{1,3}\\.\\d{1,3}$").unwrap();
+        assert!(regex.matches("192.168.0.1"))
+    }
+
+    #[test]
+    fn escaped_dot_doesnt_match_other_chars() {
+        let regex = Regex::new("^\\d{1,3}\\.\\d{1,3}\\.\\d{1,3}\\.\\d{1,3}$").unwrap();
+        assert!(!regex.matches("192x168x0x1"))
+    }
+
+    #[test]
+    fn escaped_file_path_matches() {
+        let regex = Regex::new("/var/log/app\\.log\\.\\d+").unwrap();
+        assert!(regex.matches("rotated /var/log/app.log.3"))
+    }
+
+    #[test]
+    fn escaped_group_metacharacters_match() {
+        let regex = Regex::new("^f\\(x\\) \\| \\[y\\]\\?$").unwrap();
+        assert!(regex.matches("f(x) | [y]?"))
+    }
+
+    #[test]
+    fn escaped_anchors_match_literally() {
+        let regex = Regex::new("\\$\\d+\\^").unwrap();
+        assert!(regex.matches("costs $12^"))
+    }
+
+    #[test]
+    fn escaped_quantifiers_match_literally() {
+        let regex = Regex::new("a\\+b\\*").unwrap();
+        assert!(!regex.matches("aab"))
+    }
+
+    #[test]
+    fn tab_escape_matches() {
+        let regex = Regex::new("a\\tb").unwrap();
+        assert!(regex.matches("a\tb"))
+    }
+
+    #[test]
+    fn hex_escapes_match() {
+        let regex = Regex::new("\\x41\\x{e9}\\u{1F600}").unwrap();
+        assert!(regex.matches("Aé😀"))
+    }
+
+    #[test]
+    fn alternation_of_quantified_classes_matches() {
+        let regex = Regex::new("^(\\d+|[a-z]+)$").unwrap();
+        assert!(regex.matches("12345"));
+        assert!(regex.matches("abc"))
+    }
+
+    #[test]
+    fn alternation_of_quantified_classes_doesnt_match() {
+        let regex = Regex::new("^(\\d+|[a-z]+)$").unwrap();
+        assert!(!regex.matches("abc123"))
+    }
+
+    #[test]
+    fn alternation_without_group_matches() {
+        let regex = Regex::new("foo|bar").unwrap();
+        assert!(regex.matches("a bar"))
+    }
+
+    #[test]
+    fn alternation_without_group_doesnt_match() {
+        let regex = Regex::new("foo|bar").unwrap();
+        assert!(!regex.matches("baz"))
+    }
+
+    #[test]
+    fn nested_alternation_matches() {
+        let regex = Regex::new("^(a|(b|c))+$").unwrap();
+        assert!(regex.matches("abcacb"))
+    }
+
+    #[test]
+    fn nested_alternation_doesnt_match() {
+        let regex = Regex::new("^(a|(b|c))+$").unwrap();
+        assert!(!regex.matches("abcd"))
+    }
+
+    #[test]
+    fn anchored_alternation_branches_match() {
+        let regex = Regex::new("^start|end$").unwrap();
+        assert!(regex.matches("start of line"));
+        assert!(regex.matches("line end"))
+    }
+
+    #[test]
+    fn anchored_alternation_branches_dont_match() {
+        let regex = Regex::new("^start|end$").unwrap();
+        assert!(!regex.matches("restart the endpoint"))
+    }
+
+    #[test]
+    fn alternation_with_backreference_in_branch_matches() {
+        let regex = Regex::new("^(\\w)x\\1|y$").unwrap();
+        assert!(regex.matches("axa"));
+        assert!(regex.matches("y"))
+    }
+
+    #[test]
+    fn non_capturing_group_matches() {
+        let regex = Regex::new("^(?:ab)+$").unwrap();
+        assert!(regex.matches("ababab"))
+    }
+
+    #[test]
+    fn non_capturing_group_doesnt_shift_backreferences() {
+        let regex = Regex::new("(?:cat|dog) (\\w+) \\1").unwrap();
+        assert!(regex.matches("dog runs runs"))
+    }
+
+    #[test]
+    fn non_capturing_group_doesnt_shift_backreferences_doesnt_match() {
+        let regex = Regex::new("(?:cat|dog) (\\w+) \\1").unwrap();
+        assert!(!regex.matches("dog runs dog"))
+    }
+
+    #[test]
+    fn named_backreference_matches() {
+        let regex = Regex::new("^(?<word>\\w+) and \\k<word>$").unwrap();
+        assert!(regex.matches("this and this"))
+    }
+
+    #[test]
+    fn named_backreference_doesnt_match() {
+        let regex = Regex::new("^(?<word>\\w+) and \\k<word>$").unwrap();
+        assert!(!regex.matches("this and that"))
+    }
+
+    #[test]
+    fn named_backreference_before_its_group_matches_later_repetitions() {
+        let regex = Regex::new("^(?:b\\k<n>|(?<n>a))+$").unwrap();
+        assert!(regex.matches("aba"));
+        assert!(!regex.matches("ba"))
+    }
+
+    #[test]
+    fn python_style_named_group_matches() {
+        let regex = Regex::new("(?P<quote>['\"]).*\\k<quote>").unwrap();
+        assert!(regex.matches("say 'hi'"))
+    }
+
+    #[test]
+    fn named_and_numbered_backreferences_match() {
+        let regex = Regex::new("^(?<first>a)(b)\\2\\k<first>\\1$").unwrap();
+        assert!(regex.matches("abbaa"))
+    }
+
+    #[test]
+    fn multi_digit_backreference_matches() {
+        let regex = Regex::new("^(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\\10$").unwrap();
+        assert!(regex.matches("abcdefghijj"))
+    }
+
+    #[test]
+    fn multi_digit_backreference_is_not_group_one_and_digit() {
+        let regex = Regex::new("^(a)(b)(c)(d)(e)(f)(g)(h)(i)(j)\\10$").unwrap();
+        assert!(!regex.matches("abcdefghija0"))
+    }
+
+    #[test]
+    fn backreference_to_non_participating_group_does_not_match() {
+        let regex = Regex::new("^(a)?b\\1$").unwrap();
+        assert!(!regex.matches("b"))
+    }
+
+    #[test]
+    fn positive_lookahead_matches() {
+        let regex = Regex::new("^\\w+(?=@)").unwrap();
+        assert!(regex.matches("user@example.com"))
+    }
+
+    #[test]
+    fn positive_lookahead_does_not_consume() {
+        let regex = Regex::new("^(?=\\d{3})\\d+$").unwrap();
+        assert!(regex.matches("12345"))
+    }
+
+    #[test]
+    fn positive_lookahead_does_not_match() {
+        let regex = Regex::new("^(?=\\d{3})\\d+$").unwrap();
+        assert!(!regex.matches("12"))
+    }
+
+    #[test]
+    fn negative_lookahead_matches() {
+        let regex = Regex::new("password(?!=\\*\\*\\*)").unwrap();
+        assert!(regex.matches("password=hunter2"))
+    }
+
+    #[test]
+    fn negative_lookahead_does_not_match() {
+        let regex = Regex::new("password(?!=\\*\\*\\*)").unwrap();
+        assert!(!regex.matches("password=***"))
+    }
+
+    #[test]
+    fn positive_lookbehind_matches() {
+        let regex = Regex::new("(?<=\\$)\\d+").unwrap();
+        assert!(regex.matches("costs $42"))
+    }
+
+    #[test]
+    fn positive_lookbehind_does_not_match() {
+        let regex = Regex::new("(?<=\\$)\\d+").unwrap();
+        assert!(!regex.matches("costs 42"))
+    }
+
+    #[test]
+    fn variable_length_lookbehind_matches() {
+        let regex = Regex::new("(?<=ab{1,3})c").unwrap();
+        assert!(regex.matches("xabbbc"))
+    }
+
+    #[test]
+    fn lookbehind_on_long_input_matches() {
+        let regex = Regex::new("(?<=x)b").unwrap();
+        assert!(regex.matches(&format!("{}xb", "a".repeat(50_000))))
+    }
+
+    #[test]
+    fn negative_lookbehind_matches() {
+        let regex = Regex::new("(?<!un)done").unwrap();
+        assert!(regex.matches("all done"))
+    }
+
+    #[test]
+    fn negative_lookbehind_does_not_match() {
+        let regex = Regex::new("(?<!un)done").unwrap();
+        assert!(!regex.matches("undone"))
+    }
+
+    #[test]
+    fn lookahead_captures_are_kept() {
+        let regex = Regex::new("^(?=(\\w+))\\1!$").unwrap();
+        assert!(regex.matches("abc!"))
+    }
+
+    #[test]
+    fn wildcard_does_not_match_newline() {
+        let regex = Regex::new("a.b").unwrap();
+        assert!(!regex.matches("a\nb"))
+    }
+
+    #[test]
+    fn case_insensitive_flag_matches() {
+        let regex = Regex::new("(?i)^hello [a-z]+$").unwrap();
+        assert!(regex.matches("HeLLo WORLD"))
+    }
+
+    #[test]
+    fn case_insensitive_flag_matches_non_ascii() {
+        let regex = Regex::new("(?i)émile").unwrap();
+        assert!(regex.matches("ÉMILE"))
+    }
+
+    #[test]
+    fn case_insensitive_negated_group_does_not_match() {
+        let regex = Regex::new("(?i)^[^a]$").unwrap();
+        assert!(!regex.matches("A"))
+    }
+
+    #[test]
+    fn case_insensitive_backreference_matches() {
+        let regex = Regex::new("(?i)^(a)\\1$").unwrap();
+        assert!(regex.matches("aA"));
+        assert!(Regex::new("(?i)^(k)\\1$").unwrap().matches("k\u{212A}"));
+        assert!(!Regex::new("(?i-u)^(k)\\1$").unwrap().matches("k\u{212A}"));
+    }
+
+    #[test]
+    fn backreference_outside_case_insensitive_flag_does_not_match() {
+        let regex = Regex::new("^((?i)a)\\1$").unwrap();
+        assert!(!regex.matches("aA"))
+    }
+
+    #[test]
+    fn scoped_case_insensitive_flag_matches() {
+        let regex = Regex::new("^(?i:error): [A-Z]+$").unwrap();
+        assert!(regex.matches("ErRoR: DISK"))
+    }
+
+    #[test]
+    fn scoped_case_insensitive_flag_does_not_leak() {
+        let regex = Regex::new("^(?i:error): [A-Z]+$").unwrap();
+        assert!(!regex.matches("ERROR: disk"))
+    }
+
+    #[test]
+    fn multi_line_flag_matches() {
+        let regex = Regex::new("(?m)^b$").unwrap();
+        assert!(regex.matches("a\nb\nc"))
+    }
+
+    #[test]
+    fn without_multi_line_flag_does_not_match() {
+        let regex = Regex::new("^b$").unwrap();
+        assert!(!regex.matches("a\nb\nc"))
+    }
+
+    #[test]
+    fn dot_all_flag_matches_newline() {
+        let regex = Regex::new("(?s)a.b").unwrap();
+        assert!(regex.matches("a\nb"))
+    }
+
+    #[test]
+    fn extended_flag_matches() {
+        let regex = Regex::new("(?x) \\d{3} - \\d{4}  # phone number").unwrap();
+        assert!(regex.matches("call 555-1234"))
+    }
+
+    #[test]
+    fn empty_anchors_match_empty_input() {
+        let regex = Regex::new("^$").unwrap();
+        assert!(regex.matches(""))
+    }
+
+    #[test]
+    fn empty_anchors_do_not_match_non_empty_input() {
+        let regex = Regex::new("^$").unwrap();
+        assert!(!regex.matches("a"))
+    }
+
+    #[test]
+    fn start_anchor_alone_matches() {
+        let regex = Regex::new("^").unwrap();
+        assert!(regex.matches("anything"))
+    }
+
+    #[test]
+    fn start_anchor_before_alternation_matches() {
+        let regex = Regex::new("^(cat|dog)").unwrap();
+        assert!(regex.matches("dog food"))
+    }
+
+    #[test]
+    fn start_anchor_before_alternation_does_not_match() {
+        let regex = Regex::new("^(cat|dog)").unwrap();
+        assert!(!regex.matches("hotdog"))
+    }
+
+    #[test]
+    fn anchors_inside_alternation_match() {
+        let regex = Regex::new("(^a|b$)").unwrap();
+        assert!(regex.matches("xxb") && regex.matches("axx"))
+    }
+
+    #[test]
+    fn anchors_inside_alternation_do_not_match() {
+        let regex = Regex::new("(^a|b$)").unwrap();
+        assert!(!regex.matches("xaxbx"))
+    }
+
+    #[test]
+    fn end_anchor_after_quantified_group_matches() {
+        let regex = Regex::new("(ab)+$").unwrap();
+        assert!(regex.matches("xabab"))
+    }
+
+    #[test]
+    fn end_anchor_after_quantified_group_does_not_match() {
+        let regex = Regex::new("(ab)+$").unwrap();
+        assert!(!regex.matches("ababx"))
+    }
+
+    #[test]
+    fn anchor_in_middle_does_not_match() {
+        let regex = Regex::new("a^b").unwrap();
+        assert!(!regex.matches("ab"))
+    }
+
+    #[test]
+    fn escaped_anchors_match() {
+        let regex = Regex::new("\\Aabc\\z").unwrap();
+        assert!(regex.matches("abc"))
+    }
+
+    #[test]
+    fn end_of_string_before_newline_matches() {
+        let regex = Regex::new("abc\\Z").unwrap();
+        assert!(regex.matches("abc\n"))
+    }
+
+    #[test]
+    fn end_of_string_does_not_match_before_newline() {
+        let regex = Regex::new("abc\\z").unwrap();
+        assert!(!regex.matches("abc\n"))
+    }
+
+    #[test]
+    fn digit_class_does_not_match_truncated_char() {
+        // U+0131 used to be truncated to its low byte, the ASCII `1`
+        let regex = Regex::new("\\d").unwrap();
+        assert!(!regex.matches("\u{0131}"))
+    }
+
+    #[test]
+    fn digit_class_matches_non_ascii_digits() {
+        let regex = Regex::new("^\\d+$").unwrap();
+        assert!(regex.matches("٣٤٥"))
+    }
+
+    #[test]
+    fn alphanumeric_class_matches_non_ascii_letters() {
+        let regex = Regex::new("^\\w+$").unwrap();
+        assert!(regex.matches("árvíztűrő"))
+    }
+
+    #[test]
+    fn ascii_only_alphanumeric_class_does_not_match_non_ascii_letters() {
+        let regex = Regex::new("(?-u)^\\w+$").unwrap();
+        assert!(!regex.matches("árvíztűrő"))
+    }
+
+    #[test]
+    fn word_boundary_around_non_ascii_word() {
+        let regex = Regex::new("\\bűr\\b").unwrap();
+        assert!(!regex.matches("tűrő"))
+    }
+
+    #[test]
+    fn case_insensitive_matches_case_folded_chars() {
+        let regex = Regex::new("(?i)^straſe$").unwrap();
+        assert!(regex.matches("STRASE"))
+    }
+
+    #[test]
+    fn ascii_only_case_insensitive_does_not_match_non_ascii() {
+        let regex = Regex::new("(?i-u)émile").unwrap();
+        assert!(!regex.matches("ÉMILE"))
+    }
+
+    #[test]
+    fn unicode_letter_property_matches() {
+        let regex = Regex::new("^\\p{L}+$").unwrap();
+        assert!(regex.matches("Ωmega"))
+    }
+
+    #[test]
+    fn unicode_letter_property_does_not_match_letter_number() {
+        let regex = Regex::new("\\p{L}").unwrap();
+        assert!(!regex.matches("Ⅻ"))
+    }
+
+    #[test]
+    fn unicode_general_category_groups_match() {
+        let regex = Regex::new("^\\p{P}\\p{S}e\\p{M}\\p{Lt}$").unwrap();
+        assert!(regex.matches("!€e\u{0301}ǅ"))
+    }
+
+    #[test]
+    fn greek_script_property_matches() {
+        let regex = Regex::new("^\\p{Greek}+$").unwrap();
+        assert!(regex.matches("λόγος"))
+    }
+
+    #[test]
+    fn greek_script_property_does_not_match_latin() {
+        let regex = Regex::new("\\p{Greek}").unwrap();
+        assert!(!regex.matches("logos"))
+    }
+
+    #[test]
+    fn negated_unicode_property_matches() {
+        let regex = Regex::new("^\\P{L}+$").unwrap();
+        assert!(regex.matches("123 !"))
+    }
+
+    #[test]
+    fn case_insensitive_unicode_property_matches_other_case() {
+        let regex = Regex::new("(?i)^\\p{Ll}\\p{Lu}$").unwrap();
+        assert!(regex.matches("AB"));
+        assert!(regex.matches("ab"))
+    }
+
+    #[test]
+    fn case_insensitive_negated_unicode_property_rejects_every_case() {
+        let regex = Regex::new("(?i)\\P{Ll}").unwrap();
+        assert!(!regex.matches("aA"));
+        assert!(regex.matches("1"))
+    }
+
+    #[test]
+    fn nested_repetitions_too_large_to_unroll_are_invalid() {
+        assert!(Regex::new("^((a{1000}){1000}){1000}$").is_err());
+    }
+
+    #[test]
+    fn nested_repetitions_with_backreference_match() {
+        let regex = Regex::new("^((a{1000}){1000}){1000}\\1$").unwrap();
+        assert!(!regex.matches("aaaa"));
+    }
+
+    #[test]
+    fn long_input_repeating_group_with_backreference_matches() {
+        let regex = Regex::new("(ab)+\\1").unwrap();
+        assert!(regex.matches(&"ab".repeat(200_000)))
+    }
+
+    #[test]
+    fn long_input_lazy_repeating_alternation_with_lookahead_matches() {
+        let regex = Regex::new("^(?:a|b)+?(?=c)").unwrap();
+        assert!(regex.matches(&format!("{}c", "ab".repeat(200_000))))
+    }
+}
+
+#[cfg(test)]
+mod builder_tests {
+    use codecrafters_grep::RegexBuilder;
+
+    #[test]
+    fn small_dfa_size_limit_matches() {
+        let regex = RegexBuilder::new("^ERROR.+timeout$")
+            .dfa_size_limit(16)
+            .build()
+            .unwrap();
+        assert!(regex.matches("ERROR: connection timeout"))
+    }
+
+    #[test]
+    fn small_dfa_size_limit_doesnt_match() {
+        let regex = RegexBuilder::new("^ERROR.+timeout$")
+            .dfa_size_limit(16)
+            .build()
+            .unwrap();
+        assert!(!regex.matches("ERROR: connection timeout!"))
+    }
+
+    #[test]
+    fn repeated_searches_match() {
+        let regex = RegexBuilder::new("(cat|dog)s? \\d+").build().unwrap();
+
+        assert!(regex.matches("3 cats 4"));
+        assert!(!regex.matches("3 cows 4"));
+        assert!(regex.matches("1 dog 12"));
+    }
+}
+
+#[cfg(test)]
+mod bytes_tests {
+    use codecrafters_grep::bytes::Regex;
+
+    #[test]
+    fn matches_latin1_input() {
+        let regex = Regex::new("caf.").unwrap();
+        assert!(regex.matches(b"caf\xE9"))
+    }
+
+    #[test]
+    fn matches_utf8_input() {
+        let regex = Regex::new("^t\\wz$").unwrap();
+        assert!(regex.matches("tűz".as_bytes()))
+    }
+
+    #[test]
+    fn byte_escape_matches_raw_byte() {
+        let regex = Regex::new("(?-u)\\xFF\\x00").unwrap();
+        assert!(regex.matches(b"binary \xFF\x00 data"))
+    }
+
+    #[test]
+    fn byte_escape_does_not_match_other_byte() {
+        let regex = Regex::new("(?-u)\\xFF").unwrap();
+        assert!(!regex.matches(b"\xFE"))
+    }
+
+    #[test]
+    fn byte_range_matches_raw_byte() {
+        let regex = Regex::new("(?-u)^[\\x80-\\xFF]+$").unwrap();
+        assert!(regex.matches(b"\x80\xA0\xFF"))
+    }
+
+    #[test]
+    fn byte_escape_does_not_match_byte_of_valid_utf8() {
+        let regex = Regex::new("(?-u)\\xC3").unwrap();
+        assert!(!regex.matches("é".as_bytes()))
+    }
+
+    #[test]
+    fn byte_escape_does_not_match_char() {
+        let regex = Regex::new("(?-u)^\\xFF$").unwrap();
+        assert!(!regex.matches("\u{10FFFF}".as_bytes()))
+    }
+
+    #[test]
+    fn range_from_ascii_to_byte_matches_ascii_and_raw_bytes() {
+        let regex = Regex::new("(?-u)^[\\x00-\\xFF]+$").unwrap();
+        assert!(regex.matches(b"a\x00\x80\xFF"));
+        assert!(!regex.matches("é".as_bytes()))
+    }
+
+    #[test]
+    fn backreference_matches_same_raw_byte() {
+        let regex = Regex::new("(?-u)^(.)\\1$").unwrap();
+        assert!(regex.matches(b"\xFE\xFE"));
+        assert!(!regex.matches(b"\xFE\xFF"))
+    }
+
+    #[test]
+    fn lookbehind_steps_back_over_raw_bytes() {
+        let regex = Regex::new("(?<=\\xFF.)b").unwrap();
+        assert!(regex.matches("\u{FF}\u{FF}b".as_bytes()));
+        assert!(!regex.matches(b"\xFF\xFFb"))
+    }
+
+    #[test]
+    fn unicode_hex_escape_matches_char() {
+        let regex = Regex::new("\\xFF").unwrap();
+        assert!(regex.matches("ÿ".as_bytes()))
+    }
+
+    #[test]
+    fn unicode_hex_escape_does_not_match_raw_byte() {
+        let regex = Regex::new("\\xFF").unwrap();
+        assert!(!regex.matches(b"\xFF"))
+    }
+}
+
+#[cfg(test)]
+mod find_tests {
+    use codecrafters_grep::Regex;
+
+    #[test]
+    fn find_returns_span() {
+        let regex = Regex::new("\\d+").unwrap();
+        let found = regex.find("abc 123 def").unwrap();
+
+        assert_eq!((4, 7, "123"), (found.start(), found.end(), found.as_str()));
+    }
+
+    #[test]
+    fn find_returns_none_without_match() {
+        let regex = Regex::new("\\d+").unwrap();
+        assert!(regex.find("abc").is_none())
+    }
+
+    #[test]
+    fn find_uses_byte_offsets() {
+        let regex = Regex::new("z").unwrap();
+        assert_eq!(Some(3..4), regex.find("tűz").map(|found| found.range()))
+    }
+
+    #[test]
+    fn find_prefers_first_alternative() {
+        let regex = Regex::new("a|ab").unwrap();
+        assert_eq!(Some("a"), regex.find("ab").map(|found| found.as_str()))
+    }
+
+    #[test]
+    fn find_with_lazy_quantifier() {
+        let regex = Regex::new("<.+?>").unwrap();
+        assert_eq!(Some("<a>"), regex.find("<a><b>").map(|found| found.as_str()))
+    }
+
+    #[test]
+    fn find_iter_returns_non_overlapping_matches() {
+        let regex = Regex::new("aa").unwrap();
+        let found: Vec<_> = regex.find_iter("aaaaa").map(|found| found.range()).collect();
+
+        assert_eq!(vec![0..2, 2..4], found);
+    }
+
+    #[test]
+    fn find_iter_handles_empty_matches() {
+        let regex = Regex::new("a*").unwrap();
+        let found: Vec<_> = regex.find_iter("baaab").map(|found| found.range()).collect();
+
+        assert_eq!(vec![0..0, 1..4, 5..5], found);
+    }
+
+    #[test]
+    fn find_iter_handles_empty_matches_between_non_ascii_chars() {
+        let regex = Regex::new("x?").unwrap();
+        let found: Vec<_> = regex.find_iter("éő").map(|found| found.start()).collect();
+
+        assert_eq!(vec![0, 2, 4], found);
+    }
+
+    #[test]
+    fn find_iter_keeps_lookbehind_context() {
+        let regex = Regex::new("(?<=a)b").unwrap();
+        let found: Vec<_> = regex.find_iter("abab").map(|found| found.start()).collect();
+
+        assert_eq!(vec![1, 3], found);
+    }
+
+    #[test]
+    fn find_iter_with_anchor_only_matches_once() {
+        let regex = Regex::new("^a").unwrap();
+        assert_eq!(1, regex.find_iter("aaa").count())
+    }
+
+    #[test]
+    fn find_with_nested_alternation_loop_takes_linear_time() {
+        let regex = Regex::new("(a|a)*c|b").unwrap();
+        let input = format!("{}b", "a".repeat(27));
+        let found = regex.find(&input).unwrap();
+
+        assert_eq!((27, 28), (found.start(), found.end()));
+    }
+
+    #[test]
+    fn find_iter_with_nested_quantifier_takes_linear_time() {
+        let regex = Regex::new("x|(a+)+$").unwrap();
+        let input = format!("{}bxc", "a".repeat(28));
+
+        let found: Vec<_> = regex.find_iter(&input).map(|found| found.as_str()).collect();
+
+        assert_eq!(vec!["x"], found);
+        assert_eq!(2, regex.split(&input).count());
+    }
+
+    #[test]
+    fn find_with_lazy_loop_in_repeated_group_stops_at_empty_repetition() {
+        let regex = Regex::new("(a*?)+").unwrap();
+        let found = regex.find("aa").unwrap();
+
+        assert_eq!((0, 0), (found.start(), found.end()));
+    }
+}
+
+#[cfg(test)]
+mod captures_tests {
+    use codecrafters_grep::Regex;
+
+    #[test]
+    fn captures_positional_groups() {
+        let regex = Regex::new("(\\d+)-(\\d+)").unwrap();
+        let captures = regex.captures("tel: 555-1234").unwrap();
+
+        assert_eq!(("555-1234", "555", "1234"), (&captures[0], &captures[1], &captures[2]));
+    }
+
+    #[test]
+    fn captures_named_groups() {
+        let regex = Regex::new("(?<year>\\d{4})-(?P<month>\\d{2})").unwrap();
+        let captures = regex.captures("2024-05").unwrap();
+
+        assert_eq!(("2024", "05"), (&captures["year"], &captures["month"]));
+    }
+
+    #[test]
+    fn captures_group_spans() {
+        let regex = Regex::new("a(b)c").unwrap();
+        let captures = regex.captures("xabc").unwrap();
+
+        assert_eq!(Some(2..3), captures.get(1).map(|group| group.range()));
+    }
+
+    #[test]
+    fn captures_non_participating_group_is_none() {
+        let regex = Regex::new("(a)|(b)").unwrap();
+        let captures = regex.captures("b").unwrap();
+
+        assert!(captures.get(1).is_none() && captures.get(2).is_some())
+    }
+
+    #[test]
+    fn captures_empty_repetition_of_optional_group() {
+        // The lookahead makes the backtracking matcher run instead of the Pike VM
+        for pattern in ["(a*)?", "(?=)(a*)?"] {
+            let regex = Regex::new(pattern).unwrap();
+            let captures = regex.captures("b").unwrap();
+
+            assert_eq!(Some(0..0), captures.get(1).map(|group| group.range()), "{pattern}");
+        }
+    }
+
+    #[test]
+    fn captures_unknown_group_is_none() {
+        let regex = Regex::new("(?<a>x)").unwrap();
+        let captures = regex.captures("x").unwrap();
+
+        assert!(captures.get(2).is_none() && captures.name("b").is_none())
+    }
+
+    #[test]
+    fn captures_last_repetition_of_group() {
+        let regex = Regex::new("(\\w)+").unwrap();
+        assert_eq!("c", &regex.captures("abc").unwrap()[1])
+    }
+
+    #[test]
+    fn captures_returns_none_without_match() {
+        let regex = Regex::new("(a)").unwrap();
+        assert!(regex.captures("b").is_none())
+    }
+
+    #[test]
+    fn captures_iter_returns_every_match() {
+        let regex = Regex::new("(?<key>\\w+)=(?<value>\\w+)").unwrap();
+        let pairs: Vec<_> = regex
+            .captures_iter("a=1 b=2")
+            .map(|captures| (captures["key"].to_string(), captures["value"].to_string()))
+            .collect();
+
+        assert_eq!(
+            vec![("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())],
+            pairs
+        );
+    }
+
+    #[test]
+    fn captures_len_counts_whole_match() {
+        let regex = Regex::new("(a)(?:b)(?<c>c)").unwrap();
+        assert_eq!(3, regex.captures_len())
+    }
+
+    #[test]
+    fn capture_names_by_group_number() {
+        let regex = Regex::new("(a)(?<c>c)").unwrap();
+        assert_eq!(vec![None, None, Some("c")], regex.capture_names().collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod replace_tests {
+    use codecrafters_grep::{Captures, Regex};
+    use std::borrow::Cow;
+
+    #[test]
+    fn replace_replaces_first_match() {
+        let regex = Regex::new("\\d").unwrap();
+        assert_eq!("a#b2c3", regex.replace("a1b2c3", "#"))
+    }
+
+    #[test]
+    fn replace_all_replaces_every_match() {
+        let regex = Regex::new("\\d").unwrap();
+        assert_eq!("a#b#c#", regex.replace_all("a1b2c3", "#"))
+    }
+
+    #[test]
+    fn replacen_replaces_limited_matches() {
+        let regex = Regex::new("\\d").unwrap();
+        assert_eq!("a#b#c3", regex.replacen("a1b2c3", 2, "#"))
+    }
+
+    #[test]
+    fn replace_without_match_borrows_input() {
+        let regex = Regex::new("\\d").unwrap();
+        assert!(matches!(regex.replace_all("abc", "#"), Cow::Borrowed("abc")))
+    }
+
+    #[test]
+    fn replace_expands_numbered_groups() {
+        let regex = Regex::new("(\\w+) (\\w+)").unwrap();
+        assert_eq!("world hello", regex.replace("hello world", "$2 $1"))
+    }
+
+    #[test]
+    fn replace_expands_named_groups() {
+        let regex = Regex::new("(?<key>\\w+)=(?<value>\\w+)").unwrap();
+        assert_eq!("key=***", regex.replace_all("key=secret", "${key}=***"))
+    }
+
+    #[test]
+    fn replace_expands_braced_number_before_letter() {
+        let regex = Regex::new("(a)").unwrap();
+        assert_eq!("ab", regex.replace("a", "${1}b"))
+    }
+
+    #[test]
+    fn replace_expands_unbraced_reference_greedily() {
+        let regex = Regex::new("(a)").unwrap();
+        assert_eq!("", regex.replace("a", "$1b"))
+    }
+
+    #[test]
+    fn replace_expands_escaped_dollar() {
+        let regex = Regex::new("\\d+").unwrap();
+        assert_eq!("$5 and $", regex.replace("5 and $", "$$$0"))
+    }
+
+    #[test]
+    fn replace_keeps_lone_dollar() {
+        let regex = Regex::new("x").unwrap();
+        assert_eq!("$ ${y", regex.replace("x ${y", "$"))
+    }
+
+    #[test]
+    fn replace_non_participating_group_with_empty_string() {
+        let regex = Regex::new("(a)|(b)").unwrap();
+        assert_eq!("[]", regex.replace("b", "[$1]"))
+    }
+
+    #[test]
+    fn replace_all_with_closure() {
+        let regex = Regex::new("\\d+").unwrap();
+        let replaced = regex.replace_all("1 2 3", |captures: &Captures| {
+            (captures[0].parse::<u32>().unwrap() * 2).to_string()
+        });
+
+        assert_eq!("2 4 6", replaced)
+    }
+
+    #[test]
+    fn replace_all_with_empty_matches() {
+        let regex = Regex::new("x*").unwrap();
+        assert_eq!("-a-b-", regex.replace_all("ab", "-"))
+    }
+}
+
+#[cfg(test)]
+mod split_tests {
+    use codecrafters_grep::Regex;
+
+    #[test]
+    fn split_between_matches() {
+        let regex = Regex::new("[,;]\\s*").unwrap();
+        assert_eq!(vec!["a", "b", "c"], regex.split("a, b;c").collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn split_keeps_empty_pieces_at_ends() {
+        let regex = Regex::new(",").unwrap();
+        assert_eq!(vec!["", "a", ""], regex.split(",a,").collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn split_without_match_returns_input() {
+        let regex = Regex::new(",").unwrap();
+        assert_eq!(vec!["abc"], regex.split("abc").collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn split_empty_input() {
+        let regex = Regex::new(",").unwrap();
+        assert_eq!(vec![""], regex.split("").collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn splitn_limits_pieces() {
+        let regex = Regex::new("\\s+").unwrap();
+        assert_eq!(
+            vec!["GET", "/index.html HTTP/1.1"],
+            regex.splitn("GET /index.html HTTP/1.1", 2).collect::<Vec<_>>()
+        )
+    }
+
+    #[test]
+    fn splitn_with_more_room_than_pieces() {
+        let regex = Regex::new(",").unwrap();
+        assert_eq!(vec!["a", "b"], regex.splitn("a,b", 5).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn splitn_zero_returns_nothing() {
+        let regex = Regex::new(",").unwrap();
+        assert_eq!(0, regex.splitn("a,b", 0).count())
+    }
+
+    #[test]
+    fn splitn_one_returns_input() {
+        let regex = Regex::new(",").unwrap();
+        assert_eq!(vec!["a,b"], regex.splitn("a,b", 1).collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod engine_tests {
+    use codecrafters_grep::Regex;
+    use std::ops::Range;
+
+    const INPUTS: [&str; 10] = ["", "a", "aa", "ab", "aab", "bba", "baab", "abcd", "a a", "aaba"];
+
+    fn spans(regex: &Regex, input: &str) -> Vec<Vec<Option<Range<usize>>>> {
+        regex
+            .captures_iter(input)
+            .map(|captures| {
+                (0..regex.captures_len())
+                    .map(|index| captures.get(index).map(|group| group.range()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Patterns without backreferences or lookarounds run on the Pike VM. A lookahead
+    /// matching anything in front makes the backtracking matcher run instead, which has
+    /// to find the same matches with the same groups.
+    fn assert_engines_agree(patterns: &[&str]) {
+        for pattern in patterns {
+            let pike_vm = Regex::new(pattern).unwrap();
+            let backtracker = Regex::new(&format!("(?=){pattern}")).unwrap();
+
+            for input in INPUTS {
+                assert_eq!(
+                    spans(&backtracker, input),
+                    spans(&pike_vm, input),
+                    "{pattern} on {input:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn engines_agree_on_repeated_groups_matching_empty() {
+        assert_engines_agree(&[
+            "(a*?)+", "(a*)*", "(a*)+", "(a?)?", "(a*)?", "(|a)+", "(a|)+?b", "(a?){1,2}",
+            "(a??){2,3}", "(a*){2,}", "((a)|b?)*",
+        ]);
+    }
+
+    #[test]
+    fn engines_agree_on_nested_repetitions() {
+        assert_engines_agree(&[
+            "(?:(a*|ba*b+?)*)?(?:(?:a{2,})??)*?",
+            "(b??(a+b{2,}a*|a*?ba*?)*?|a+?(a??a*?[ab])??)+",
+            "(a(b?a*?a??|a*a+){0,})*[ab]",
+            "(?:a+)??(?:(a{0,1}){0,}|a+){0,}(a+b(a*|[ab]*?a??a+?)*)*",
+        ]);
+    }
+
+    #[test]
+    fn engines_agree_on_alternations() {
+        assert_engines_agree(&["(a|a)*c|b", "x|(a+)+$", "(a|ab)(c|bcd)(d*)", "(a|b)*?b"]);
+    }
+
+    #[test]
+    fn engines_agree_on_repeated_assertions() {
+        assert_engines_agree(&["(?:\\b|a)*", "(^|a)+", "(a|$)*?b?", "(\\B)*a"]);
+    }
 }